@@ -0,0 +1,27 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// How ut used to expand blocking functions: a new runtime for every call
+#[tokio::main]
+async fn per_call_runtime(input: u64) -> u64 {
+  input + 1
+}
+
+// How ut expands blocking functions now: one runtime shared by every call
+#[ut::wrap]
+async fn shared_runtime(input: u64) -> u64 {
+  input + 1
+}
+
+fn blocking_call(c: &mut Criterion) {
+  let mut group = c.benchmark_group("blocking_call");
+  group.bench_function("per_call_runtime", |b| {
+    b.iter(|| per_call_runtime(black_box(1)))
+  });
+  group.bench_function("shared_runtime", |b| {
+    b.iter(|| shared_runtime(black_box(1)))
+  });
+  group.finish();
+}
+
+criterion_group!(benches, blocking_call);
+criterion_main!(benches);
@@ -15,6 +15,9 @@
 //! you create a sync feature. When this feature is enabled ut will create
 //! synchronous functions on what you have wrapped.
 //!
+//! Crates that call this feature something else or want another condition can
+//! pass any cfg predicate with `cfg`, like `#[ut::wrap(cfg = "feature = \"blocking\"")]`.
+//!
 //! you can either: 
 //! - Replace your asynchronous function with a synchronous one
 //! - Clone your asynchronous function with a synchronous one ending in _blocking
@@ -70,7 +73,8 @@
 //!
 //! // You also need to create the struct to place the cloned impls in
 //! // This is done so you can choose what structs/impls to clone/wrap
-//! // The cloned structs/impls should end in Blocking
+//! // The cloned structs/impls should end in Blocking unless they are named
+//! // with the target, prefix or suffix arguments
 //! #[derive(Default)]
 //! pub struct ExampleBlocking {
 //!   pub fooers: FooersBlocking,
@@ -86,7 +90,7 @@
 //! pub struct FooersBlocking;
 //!
 //! // The async impls that you want to wrap
-//! // All methods within this impl must be async
+//! // Async methods get a blocking version, other items are copied as is
 //! #[ut::clone_impl]
 //! impl Fooers {
 //!   pub async fn foo(&self, input: &str) -> String {
@@ -104,178 +108,193 @@
 //! assert_eq!(out, "I am also sync now".to_owned())
 //! ```
 //!
-//! Currently the wrapping is very naive and simply wraps the function in
-//! tokio::main. This is likely more expensive then it needs to be and I hope
-//! to make it more efficient later.
-
-use syn;
-use quote::quote;
-use proc_macro::TokenStream;
-
-/// Wraps an async function in order to make it synchronous
-///
-/// # Examples
-///
-/// ```
-/// #[ut::wrap]
-/// async fn foo(input: &str) -> String {
-///  format!("I am {} now", input)
-/// }
-///
-/// let out = foo("sync");
-/// assert_eq!(out, "I am sync now".to_owned())
-/// ```
-#[proc_macro_attribute]
-pub fn wrap(_meta: TokenStream, input: TokenStream) -> TokenStream {
-  // parse the input stream into our async function
-  let func = syn::parse_macro_input!(input as syn::ItemFn);
-  // get attributes (docstrings/examples) for our function
-  let attrs = &func.attrs;
-  // get visibility of function
-  let vis = &func.vis;
-  // get the name of our function
-  let name = &func.sig.ident;
-  // get information on the generics to pass
-  let generics = &func.sig.generics;
-  // get the arguments for our function
-  let args = &func.sig.inputs;
-  // get our output
-  let output = &func.sig.output;
-  // get the block of instrutions that are going to be called
-  let block = &func.block;
-  // cast back to a token stream
-  let output = quote!{
-    // iterate and add all of our attributes
-    #(#attrs)*
-    // conditionally add tokio::main if the sync feature is enabled
-    #[cfg_attr(feature = "sync", tokio::main)]
-    #vis async fn #name #generics(#args) #output { #block }
-  };
-  output.into()
-}
-
-/// Clones an async function in order to make it also synchronous
-///
-/// This will add _blocking to the name of the function to clone.
-///
-/// # Examples
-///
-/// ```
-/// #[ut::clone]
-/// async fn foo(input: &str) -> String {
-///  format!("I am {} now", input)
-/// }
-///
-/// let out = foo_blocking("sync");
-/// assert_eq!(out, "I am sync now".to_owned())
-/// ```
-#[proc_macro_attribute]
-pub fn clone(_meta: TokenStream, input: TokenStream) -> TokenStream {
-  // parse the input stream into our async function
-  let func = syn::parse_macro_input!(input as syn::ItemFn);
-  // get attributes (docstrings/examples) for our function
-  let attrs = &func.attrs;
-  // get visibility of function
-  let vis = &func.vis;
-  // get the name of our function
-  let name = &func.sig.ident;
-  // get the name of our cloned function
-  let sync_name = syn::Ident::new(&format!("{}_blocking", name), name.span());
-  // get information on the generics to pass
-  let generics = &func.sig.generics;
-  // get the arguments for our function
-  let args = &func.sig.inputs;
-  // get our output
-  let output = &func.sig.output;
-  // get the block of instrutions that are going to be called
-  let block = &func.block;
-  // cast back to a token stream
-  let output = quote!{
-    // iterate and add all of our attributes
-    #(#attrs)*
-    // conditionally add tokio::main if the sync feature is enabled
-    #vis async fn #name #generics(#args) #output { #block }
-    
-    // iterate and add all of our attributes
-    #(#attrs)*
-    // conditionally add tokio::main if the sync feature is enabled
-    #[cfg_attr(feature = "sync", tokio::main)]
-    #vis async fn #sync_name #generics(#args) #output { #block }
-  };
-  output.into()
-}
+//! Instead of writing the blocking struct by hand it can be generated as a wrapper
+//! around the async one with ut::newtype. The blocking methods then call the async
+//! methods on the wrapped value instead of copying them:
+//!
+//! ```rust
+//! #[ut::newtype]
+//! pub struct Users {
+//!   name: String,
+//! }
+//!
+//! #[ut::clone_impl(delegate = inner)]
+//! impl Users {
+//!   pub async fn greet(&self, greeting: &str) -> String {
+//!     format!("{} {}", greeting, self.name)
+//!   }
+//! }
+//!
+//! let users = UsersBlocking::from(Users { name: "sam".to_owned() });
+//! assert_eq!(users.greet("hi"), "hi sam".to_owned());
+//! ```
+//!
+//! Structs holding async types like `Example` above can also be generated with
+//! ut::blocking_struct. Every field holds the blocking version of its type unless
+//! it is marked with `#[ut(keep)]` or `#[ut(skip)]`:
+//!
+//! ```rust
+//! #[ut::newtype]
+//! pub struct Users;
+//!
+//! #[ut::blocking_struct]
+//! pub struct Client {
+//!   pub users: Users,
+//!   #[ut(keep)]
+//!   pub url: String,
+//! }
+//!
+//! let client = ClientBlocking { users: Users.into(), url: "localhost".to_owned() };
+//! ```
+//!
+//! # Cloning async traits
+//!
+//! Traits with async methods, written natively or with async-trait, can be cloned
+//! into a blocking trait with ut::clone_trait. The blocking trait is implemented
+//! for every type implementing the async one:
+//!
+//! ```rust
+//! #[ut::clone_trait]
+//! pub trait Transport {
+//!   async fn send(&self, body: &str) -> usize;
+//! }
+//!
+//! pub struct Http;
+//!
+//! impl Transport for Http {
+//!   async fn send(&self, body: &str) -> usize {
+//!     body.len()
+//!   }
+//! }
+//!
+//! let sent = TransportBlocking::send(&Http, "hello");
+//! assert_eq!(sent, 5)
+//! ```
+//!
+//! # Runtime
+//!
+//! Every synchronous function created by ut blocks on a single tokio runtime
+//! that is lazily created the first time any of them is called. This runtime is
+//! shared by the whole process so only the first blocking call pays the cost of
+//! building it. The runtime can be tuned with [`Config`] before the first
+//! blocking call.
+//!
+//! Blocking calls use tokio by default but the executor can be swapped with one of
+//! the runtime features. Exactly one of these has to be enabled:
+//!
+//! - runtime-tokio (default): blocks on the shared tokio runtime
+//! - runtime-async-std: blocks on the async-std executor
+//! - runtime-smol: blocks with smol
+//! - runtime-futures-executor: blocks with the executor from the futures crate
+//!
+//! ```toml
+//! [dependencies]
+//! ut = { version = "0.2.0", default-features = false, features = ["runtime-smol"] }
+//! ```
+//!
+//! Blocking functions can also be called from inside of a multi threaded tokio
+//! runtime. The current worker hands its other tasks off while the call blocks on
+//! that runtime. A current thread tokio runtime is stalled until the call finishes
+//! so call them from `spawn_blocking` there. Blocking inside of executors that can
+//! not block at all such as the futures executor panics with
+//! [`BlockingError::NestedRuntime`]. Pass the `fallible` argument to any of
+//! the macros to get this error back instead:
+//!
+//! ```
+//! #[ut::clone(fallible)]
+//! async fn foo(input: &str) -> String {
+//!   format!("I am {} now", input)
+//! }
+//!
+//! let out = foo_try_blocking("sync");
+//! assert_eq!(out.unwrap(), "I am sync now".to_owned())
+//! ```
+//!
+//! Blocking functions can instead hand their futures to a runtime owned by a
+//! background thread, like `reqwest::blocking` does, with the `background`
+//! argument. These calls can be made from inside of another runtime but not
+//! from a future already running in the background. See [`background`] for
+//! more.
+//!
+//! An executor of your own can be used instead of the runtime features by passing
+//! it to any of the macros. This must be a function like
+//! `fn block_on<F: Future>(future: F) -> F::Output`:
+//!
+//! ```ignore
+//! #[ut::clone(executor = my_crate::rt::block_on)]
+//! async fn foo(input: &str) -> String {
+//!   format!("I am {} now", input)
+//! }
+//! ```
+//!
+//! # Timeouts
+//!
+//! Blocking functions can be told to give up after a while with the `timeout`
+//! argument, or for every blocking call with [`set_default_timeout`]. The async
+//! functions are left alone. Blocking functions that time out panic unless they
+//! are fallible in which case they return [`BlockingError::Timeout`]:
+//!
+//! ```rust
+//! #[ut::clone(timeout = "10ms", fallible)]
+//! async fn forever() {
+//!   std::future::pending::<()>().await
+//! }
+//!
+//! let err = forever_try_blocking().unwrap_err();
+//! assert!(matches!(err, ut::BlockingError::Timeout(_)));
+//! ```
+//!
+//! # Crate defaults
+//!
+//! Arguments used by every macro in a crate can be set once in its Cargo.toml.
+//! Arguments passed to a macro override these defaults and invalid defaults fail
+//! to compile with an error pointing at the manifest:
+//!
+//! ```toml
+//! [package.metadata.ut]
+//! # only block when this feature is enabled, or use cfg for any predicate
+//! feature = "blocking"
+//! # name clones foo_sync instead of foo_blocking
+//! suffix = "_sync"
+//! # block with our own executor instead of the shared runtime
+//! executor = "crate::rt::block_on"
+//! timeout = "30s"
+//! ```
 
+pub use ut_macros::{wrap, clone, clone_impl, clone_trait, newtype, blocking_struct};
+pub use ut_runtime::{background, block_on, try_block_on, BlockingError, Config};
+pub use ut_runtime::{block_on_timeout, try_block_on_timeout, default_timeout, set_default_timeout};
 
-/// Clones an group of async functions in an impl to a new sub structure
-///
-/// This is useful when you want to support both async and sync functions
-/// in a struct implementation.
-///
-/// # Examples
+/// Links an async type to its blocking version
 ///
-/// ```
-/// #[derive(Default)]
-/// pub struct Example {
-///   pub fooers: Fooers,
-/// }
+/// This is implemented by [`blocking_struct`] and [`newtype`] for the types
+/// they are used on, and [`blocking_struct`] uses it to find the blocking
+/// version of every field. Blocking types written by hand can be linked to
+/// their async type by implementing it:
 ///
-/// #[derive(Default)]
-/// pub struct ExampleBlocking {
-///   pub fooers: FooersBlocking,
-/// }
-///
-/// #[derive(Default)]
+/// ```rust
 /// pub struct Fooers;
 ///
-/// #[derive(Default)]
 /// pub struct FooersBlocking;
 ///
-/// #[ut::clone_impl]
-/// impl Fooers {
-///   pub async fn foo(&self, input: &str) -> String {
-///     format!("I am {} now", input)
-///   }
+/// impl ut::Blocking for Fooers {
+///   type Blocking = FooersBlocking;
 /// }
-///
-/// let out = ExampleBlocking::default().fooers.foo("sync");
-/// assert_eq!(out, "I am sync now".to_owned())
 /// ```
-#[proc_macro_attribute]
-pub fn clone_impl(_meta: TokenStream, input: TokenStream) -> TokenStream {
-  // parse the input stream into our async function
-  let imp = syn::parse_macro_input!(input as syn::ItemImpl);
-  // get attributes (docstrings/examples) for our function
-  let attrs = &imp.attrs;
-  // get the methods implemented in this impl
-  let items = &imp.items;
-  // get the self type for this impl
-  let self_ty = match *imp.self_ty {
-    syn::Type::Path(path)  =>  path,
-    _ => panic!("Only type paths are supported"),
-  };
-  // build sync name
-  let ident = self_ty.path.get_ident().unwrap();
-  let sync_name = syn::Ident::new(&format!("{}Blocking", ident), ident.span());
-  // get information on the generics to pass
-  let generics = &imp.generics;
-  // cast back to a token stream
-  let output = quote!{
-    // iterate and add all of the original async methods
-    #(#attrs)*
-    #generics 
-    impl #self_ty {
-      #(#items)*
-    }
-
-    // Clone our async methods but wrap them
-    impl #sync_name {
-      // wrap them to make the synchronous
-      #(
-        #[ut::wrap]
-        #items
-      )*
-    }  
-  };
-  output.into()
+#[diagnostic::on_unimplemented(
+  message = "`{Self}` has no blocking version",
+  note = "keep fields as is with `#[ut(keep)]` or leave them out with `#[ut(skip)]`",
+)]
+pub trait Blocking {
+  /// The blocking version of this type
+  type Blocking;
 }
 
+/// Support code for the functions generated by the ut macros
+///
+/// This is not part of the public api and can change at any time.
+#[doc(hidden)]
+pub mod __private {
+  pub use ut_runtime::{background, block_on, try_block_on, BlockingError, Config};
+  pub use ut_runtime::{block_on_timeout, try_block_on_timeout, expect, timeout, timeout_default};
+}
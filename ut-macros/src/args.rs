@@ -0,0 +1,719 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+use crate::metadata::Defaults;
+use crate::types::TypeMap;
+
+/// The arguments that can be passed to the ut macros
+#[derive(Clone, Default)]
+pub(crate) struct Args {
+  /// The function to block on futures with instead of the shared runtime
+  pub executor: Option<syn::Path>,
+  /// Whether to block on the runtime owned by the background thread
+  pub background: Option<syn::Ident>,
+  /// Whether blocking functions return errors instead of panicking
+  pub fallible: Option<syn::Ident>,
+  /// How long blocking functions wait before giving up
+  pub timeout: Option<Timeout>,
+  /// The cfg predicate that enables blocking functions
+  pub cfg: Option<Cfg>,
+  /// The manifest the crate wide defaults were read from
+  pub manifest: Option<syn::LitStr>,
+}
+
+/// A cfg predicate passed to the ut macros like `cfg = "feature = \"blocking\""`
+#[derive(Clone)]
+pub(crate) struct Cfg {
+  /// The parsed predicate
+  meta: syn::Meta,
+}
+
+impl Parse for Cfg {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    Cfg::from_lit(input.parse()?)
+  }
+}
+
+impl Cfg {
+  /// Parses a cfg predicate from a string like "feature = \"blocking\""
+  pub fn from_lit(lit: syn::LitStr) -> syn::Result<Self> {
+    match lit.parse() {
+      Ok(meta) => Ok(Cfg { meta }),
+      Err(_) => {
+        let msg = format!(
+          "invalid cfg predicate `{}`, expected something like `feature = \"blocking\"`",
+          lit.value(),
+        );
+        Err(syn::Error::new(lit.span(), msg))
+      },
+    }
+  }
+}
+
+/// A timeout passed to the ut macros like `timeout = "30s"`
+#[derive(Clone)]
+pub(crate) struct Timeout {
+  /// The timeout in milliseconds
+  millis: u64,
+}
+
+impl Parse for Timeout {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    Timeout::from_lit(input.parse()?)
+  }
+}
+
+impl Timeout {
+  /// Parses a timeout from a string like "30s"
+  pub fn from_lit(lit: syn::LitStr) -> syn::Result<Self> {
+    let value = lit.value();
+    // split the amount from its unit
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let scale = match unit.trim() {
+      "ms" => Some(1),
+      "s" => Some(1000),
+      "m" => Some(60 * 1000),
+      "h" => Some(60 * 60 * 1000),
+      _ => None,
+    };
+    let millis = match (amount.parse::<u64>(), scale) {
+      (Ok(amount), Some(scale)) => amount.checked_mul(scale),
+      _ => None,
+    };
+    match millis {
+      Some(millis) => Ok(Timeout { millis }),
+      None => {
+        let msg = format!(
+          "invalid timeout `{}`, expected a whole number of ms, s, m or h like \"30s\"",
+          value,
+        );
+        Err(syn::Error::new(lit.span(), msg))
+      },
+    }
+  }
+}
+
+impl ToTokens for Timeout {
+  fn to_tokens(&self, tokens: &mut TokenStream) {
+    let millis = self.millis;
+    tokens.extend(quote!(::std::time::Duration::from_millis(#millis)));
+  }
+}
+
+impl Args {
+  /// Gets the body of a blocking function that runs a future
+  ///
+  /// Fallible functions return a `Result<T, ut::BlockingError>` and can only
+  /// fail when blocking on one of ut's runtimes or when timing out.
+  pub fn blocking_body(&self, future: TokenStream, fallible: bool) -> TokenStream {
+    // executors we do not own can still be timed out by wrapping the future
+    if let Some(executor) = &self.executor {
+      return match (&self.timeout, fallible) {
+        (None, false) => {
+          quote!(::ut::__private::expect(#executor(::ut::__private::timeout_default(#future))))
+        },
+        (None, true) => quote!(#executor(::ut::__private::timeout_default(#future))),
+        (Some(timeout), false) => {
+          quote!(::ut::__private::expect(#executor(::ut::__private::timeout(#timeout, #future))))
+        },
+        (Some(timeout), true) => quote!(#executor(::ut::__private::timeout(#timeout, #future))),
+      };
+    }
+    let runtime = match &self.background {
+      Some(_) => quote!(::ut::__private::background),
+      None => quote!(::ut::__private),
+    };
+    match (&self.timeout, fallible) {
+      (None, false) => quote!(#runtime::block_on(#future)),
+      (None, true) => quote!(#runtime::try_block_on(#future)),
+      (Some(timeout), false) => quote!(#runtime::block_on_timeout(#future, #timeout)),
+      (Some(timeout), true) => quote!(#runtime::try_block_on_timeout(#future, #timeout)),
+    }
+  }
+
+  /// Gets the cfg predicate that enables blocking functions
+  ///
+  /// This is `feature = "sync"` unless another predicate was given.
+  pub fn cfg(&self) -> TokenStream {
+    match &self.cfg {
+      Some(cfg) => cfg.meta.to_token_stream(),
+      None => quote!(feature = "sync"),
+    }
+  }
+
+  /// Parses the value of a single argument, returning false if `key` is unknown
+  fn parse_arg(&mut self, key: &syn::Ident, input: ParseStream) -> syn::Result<bool> {
+    match key.to_string().as_str() {
+      "executor" => {
+        input.parse::<Token![=]>()?;
+        set(&mut self.executor, key, input.parse()?)?;
+      },
+      "background" => set(&mut self.background, key, key.clone())?,
+      "fallible" => set(&mut self.fallible, key, key.clone())?,
+      "timeout" => {
+        input.parse::<Token![=]>()?;
+        set(&mut self.timeout, key, input.parse()?)?;
+      },
+      "cfg" => {
+        input.parse::<Token![=]>()?;
+        set(&mut self.cfg, key, input.parse()?)?;
+      },
+      _ => return Ok(false),
+    }
+    Ok(true)
+  }
+
+  /// Gets a statement that makes the compiler track the manifest our defaults came from
+  ///
+  /// Cargo does not rebuild crates when only their metadata changes so this
+  /// has to be added to every function whose expansion depends on it.
+  pub fn track(&self) -> Option<TokenStream> {
+    let manifest = self.manifest.as_ref()?;
+    Some(quote!(const _: &str = include_str!(#manifest);))
+  }
+
+  /// Fills in the arguments that were not passed with the crate wide defaults
+  pub fn fill(&mut self, defaults: &mut Defaults) {
+    // a background runtime passed to the macro overrides the default executor
+    if self.executor.is_none() && self.background.is_none() {
+      self.executor = defaults.executor.take();
+    }
+    if self.timeout.is_none() {
+      self.timeout = defaults.timeout.take();
+    }
+    if self.cfg.is_none() {
+      self.cfg = defaults.cfg.take();
+    }
+    self.manifest = defaults.manifest.take();
+  }
+
+  /// Makes sure the parsed arguments can be used together
+  fn validate(&self) -> syn::Result<()> {
+    // the background runtime is an executor so only one of them can be picked
+    if let (Some(_), Some(background)) = (&self.executor, &self.background) {
+      let msg = "`background` can not be used with `executor`";
+      return Err(syn::Error::new(background.span(), msg));
+    }
+    Ok(())
+  }
+}
+
+/// Gets the signature of a blocking function from the signature of its async original
+///
+/// Everything but the name, the asyncness and the return type of fallible
+/// functions is kept, so generics, where clauses, `unsafe`, ABIs and variadics
+/// carry over untouched.
+pub(crate) fn blocking_sig(sig: &syn::Signature, name: syn::Ident, fallible: bool) -> syn::Signature {
+  let mut sync_sig = sig.clone();
+  sync_sig.asyncness = None;
+  sync_sig.ident = name;
+  // fallible functions wrap their output in a result
+  if fallible {
+    sync_sig.output = match &sig.output {
+      syn::ReturnType::Default => syn::parse_quote!(-> ::std::result::Result<(), ::ut::BlockingError>),
+      syn::ReturnType::Type(arrow, ty) => syn::parse_quote!(#arrow ::std::result::Result<#ty, ::ut::BlockingError>),
+    };
+  }
+  sync_sig
+}
+
+impl Parse for Args {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut args = Args::default();
+    let expected = "`executor`, `background`, `fallible`, `timeout` or `cfg`";
+    parse_list(input, expected, |key, input| args.parse_arg(key, input))?;
+    args.fill(&mut Defaults::load()?);
+    args.validate()?;
+    Ok(args)
+  }
+}
+
+/// A prefix and a suffix added to a name like `prefix = "Sync"`
+#[derive(Default)]
+pub(crate) struct Affixes {
+  /// The string added before the name
+  pub prefix: Option<syn::LitStr>,
+  /// The string added after the name
+  pub suffix: Option<syn::LitStr>,
+}
+
+impl Affixes {
+  /// Gets the first affix that was set if any
+  fn first(&self) -> Option<&syn::LitStr> {
+    self.prefix.as_ref().or(self.suffix.as_ref())
+  }
+
+  /// Names a `kind` after `ident`, adding `default` as a suffix if no affix was given
+  fn apply(&self, ident: &syn::Ident, default: &str, kind: &str) -> syn::Result<syn::Ident> {
+    let (prefix, suffix) = match (&self.prefix, &self.suffix) {
+      (None, None) => (String::new(), default.to_owned()),
+      (prefix, suffix) => (
+        prefix.as_ref().map(syn::LitStr::value).unwrap_or_default(),
+        suffix.as_ref().map(syn::LitStr::value).unwrap_or_default(),
+      ),
+    };
+    let name = format!("{}{}{}", prefix, ident, suffix);
+    if syn::parse_str::<syn::Ident>(&name).is_ok() {
+      return Ok(syn::Ident::new(&name, ident.span()));
+    }
+    let msg = format!("`{}` is not a valid name for the {}", name, kind);
+    // point at the affixes that made the name invalid
+    let err = match (&self.prefix, &self.suffix) {
+      (Some(prefix), Some(suffix)) => syn::Error::new_spanned(quote!(#prefix #suffix), msg),
+      (Some(prefix), None) => syn::Error::new_spanned(prefix, msg),
+      (None, Some(suffix)) => syn::Error::new_spanned(suffix, msg),
+      (None, None) => syn::Error::new_spanned(ident, msg),
+    };
+    Err(err)
+  }
+}
+
+/// The arguments that can be passed to `#[ut::clone]`
+#[derive(Default)]
+pub(crate) struct CloneArgs {
+  /// The arguments used to block in the cloned function
+  pub args: Args,
+  /// The name of the cloned function
+  pub name: Option<syn::LitStr>,
+  /// The prefix and suffix added to the name of the async function
+  pub affixes: Affixes,
+}
+
+impl CloneArgs {
+  /// Gets the name of the blocking clone of an async function called `ident`
+  ///
+  /// This is `{ident}_blocking` unless a `name` or a `prefix`/`suffix` was given.
+  pub fn sync_ident(&self, ident: &syn::Ident) -> syn::Result<syn::Ident> {
+    match &self.name {
+      Some(name) => match syn::parse_str::<syn::Ident>(&name.value()) {
+        Ok(_) => Ok(syn::Ident::new(&name.value(), name.span())),
+        Err(_) => {
+          let msg = format!("`{}` is not a valid name for the blocking function", name.value());
+          Err(syn::Error::new_spanned(name, msg))
+        },
+      },
+      None => self.affixes.apply(ident, "_blocking", "blocking function"),
+    }
+  }
+
+  /// Fills in the arguments that were not passed with the crate wide defaults
+  ///
+  /// Only clones are named with the default prefix and suffix.
+  pub fn fill(&mut self, mut defaults: Defaults) {
+    self.args.fill(&mut defaults);
+    // default names are only used if the clone was not named at all
+    if self.name.is_none() && self.affixes.first().is_none() {
+      self.affixes.prefix = defaults.prefix;
+      self.affixes.suffix = defaults.suffix;
+    }
+  }
+
+  /// Gets the name of the fallible clone of an async function called `ident`
+  ///
+  /// This is `{ident}_try_blocking` by default and the name of the blocking
+  /// clone starting with `try_` when it was renamed.
+  pub fn try_ident(&self, ident: &syn::Ident) -> syn::Result<syn::Ident> {
+    if self.name.is_none() && self.affixes.first().is_none() {
+      return Ok(syn::Ident::new(&format!("{}_try_blocking", ident), ident.span()));
+    }
+    let sync_ident = self.sync_ident(ident)?;
+    Ok(syn::Ident::new(&format!("try_{}", sync_ident), sync_ident.span()))
+  }
+}
+
+impl Parse for CloneArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut clone = CloneArgs::default();
+    let expected = "`executor`, `background`, `fallible`, `timeout`, `cfg`, `name`, `prefix` or `suffix`";
+    parse_list(input, expected, |key, input| {
+      match key.to_string().as_str() {
+        "name" => {
+          input.parse::<Token![=]>()?;
+          set(&mut clone.name, key, input.parse()?)?;
+        },
+        "prefix" => {
+          input.parse::<Token![=]>()?;
+          set(&mut clone.affixes.prefix, key, input.parse()?)?;
+        },
+        "suffix" => {
+          input.parse::<Token![=]>()?;
+          set(&mut clone.affixes.suffix, key, input.parse()?)?;
+        },
+        _ => return clone.args.parse_arg(key, input),
+      }
+      Ok(true)
+    })?;
+    clone.fill(Defaults::load()?);
+    clone.args.validate()?;
+    // a name can not be combined with a pattern
+    if let (Some(_), Some(affix)) = (&clone.name, clone.affixes.first()) {
+      let msg = "`prefix` and `suffix` can not be used with `name`";
+      return Err(syn::Error::new(affix.span(), msg));
+    }
+    Ok(clone)
+  }
+}
+
+/// The arguments that can be passed to `#[ut::clone_impl]`
+#[derive(Default)]
+pub(crate) struct ImplArgs {
+  /// The arguments passed on to every blocking method
+  pub args: Args,
+  /// The module the blocking type lives in instead of next to the async one
+  pub module: Option<syn::Path>,
+  /// The name of the blocking type
+  pub name: TypeName,
+  /// The prefix and suffix added to the name of every blocking method
+  pub method_affixes: Affixes,
+  /// The field of the blocking type holding the async value to delegate to
+  pub delegate: Option<syn::Member>,
+  /// The blocking trait implemented instead of the async trait of a trait impl
+  pub trait_target: Option<syn::Path>,
+  /// The async types blocking methods return the blocking versions of
+  pub map: Option<TypeMap>,
+}
+
+impl ImplArgs {
+  /// Gets the name of the blocking type for an async type called `ident`
+  ///
+  /// This is `{ident}Blocking` unless a `target` or a `prefix`/`suffix` was given.
+  pub fn sync_ident(&self, ident: &syn::Ident) -> syn::Result<syn::Ident> {
+    self.name.ident(ident)
+  }
+
+  /// Gets the name of the blocking method for an async method called `ident`
+  ///
+  /// Methods keep their name unless a `method_prefix`/`method_suffix` was given.
+  pub fn method_ident(&self, ident: &syn::Ident) -> syn::Result<syn::Ident> {
+    self.method_affixes.apply(ident, "", "blocking method")
+  }
+
+  /// Gets the blocking trait to implement for an impl of the async trait at `path`
+  ///
+  /// This is the trait next to it with Blocking added unless a `trait_target` was given.
+  pub fn sync_trait(&self, path: &syn::Path) -> syn::Path {
+    if let Some(target) = &self.trait_target {
+      return target.clone();
+    }
+    let mut path = path.clone();
+    if let Some(segment) = path.segments.last_mut() {
+      segment.ident = syn::Ident::new(&format!("{}Blocking", segment.ident), segment.ident.span());
+    }
+    path
+  }
+}
+
+impl Parse for ImplArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut imp = ImplArgs::default();
+    let expected = "`executor`, `background`, `fallible`, `timeout`, `cfg`, `module`, `target`, \
+                    `prefix`, `suffix`, `method_prefix`, `method_suffix`, `delegate`, `trait_target` or `map`";
+    parse_list(input, expected, |key, input| {
+      match key.to_string().as_str() {
+        "module" => {
+          input.parse::<Token![=]>()?;
+          set(&mut imp.module, key, input.parse()?)?;
+        },
+        "method_prefix" => {
+          input.parse::<Token![=]>()?;
+          set(&mut imp.method_affixes.prefix, key, input.parse()?)?;
+        },
+        "method_suffix" => {
+          input.parse::<Token![=]>()?;
+          set(&mut imp.method_affixes.suffix, key, input.parse()?)?;
+        },
+        "delegate" => {
+          input.parse::<Token![=]>()?;
+          set(&mut imp.delegate, key, input.parse()?)?;
+        },
+        "trait_target" => {
+          input.parse::<Token![=]>()?;
+          set(&mut imp.trait_target, key, input.parse()?)?;
+        },
+        "map" => {
+          let content;
+          syn::parenthesized!(content in input);
+          set(&mut imp.map, key, content.parse()?)?;
+        },
+        _ => return Ok(imp.name.parse_arg(key, input)? || imp.args.parse_arg(key, input)?),
+      }
+      Ok(true)
+    })?;
+    imp.args.fill(&mut Defaults::load()?);
+    imp.args.validate()?;
+    imp.name.validate()?;
+    Ok(imp)
+  }
+}
+
+/// The arguments that can be passed to `#[ut::clone_trait]`
+#[derive(Default)]
+pub(crate) struct TraitArgs {
+  /// The arguments passed on to every blocking method
+  pub args: Args,
+  /// The name of the blocking trait
+  pub name: TypeName,
+  /// The prefix and suffix added to the name of every blocking method
+  pub method_affixes: Affixes,
+  /// Whether types have to implement the blocking trait themselves
+  pub opt_in: Option<syn::Ident>,
+}
+
+impl TraitArgs {
+  /// Gets the name of the blocking method for an async method called `ident`
+  ///
+  /// Methods keep their name unless a `method_prefix`/`method_suffix` was given.
+  pub fn method_ident(&self, ident: &syn::Ident) -> syn::Result<syn::Ident> {
+    self.method_affixes.apply(ident, "", "blocking method")
+  }
+}
+
+impl Parse for TraitArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut tr = TraitArgs::default();
+    let expected = "`executor`, `background`, `fallible`, `timeout`, `cfg`, `target`, `prefix`, \
+                    `suffix`, `method_prefix`, `method_suffix` or `opt_in`";
+    parse_list(input, expected, |key, input| {
+      match key.to_string().as_str() {
+        "method_prefix" => {
+          input.parse::<Token![=]>()?;
+          set(&mut tr.method_affixes.prefix, key, input.parse()?)?;
+        },
+        "method_suffix" => {
+          input.parse::<Token![=]>()?;
+          set(&mut tr.method_affixes.suffix, key, input.parse()?)?;
+        },
+        "opt_in" => set(&mut tr.opt_in, key, key.clone())?,
+        _ => return Ok(tr.name.parse_arg(key, input)? || tr.args.parse_arg(key, input)?),
+      }
+      Ok(true)
+    })?;
+    tr.args.fill(&mut Defaults::load()?);
+    tr.args.validate()?;
+    tr.name.validate()?;
+    Ok(tr)
+  }
+}
+
+/// The name of a blocking type set with `target = Name` or `prefix`/`suffix`
+#[derive(Default)]
+pub(crate) struct TypeName {
+  /// The name of the blocking type
+  pub target: Option<syn::Ident>,
+  /// The prefix and suffix added to the name of the async type
+  pub affixes: Affixes,
+}
+
+impl TypeName {
+  /// Gets the name of the blocking type for an async type called `ident`
+  ///
+  /// This is `{ident}Blocking` unless a `target` or a `prefix`/`suffix` was given.
+  pub fn ident(&self, ident: &syn::Ident) -> syn::Result<syn::Ident> {
+    match &self.target {
+      Some(target) => Ok(target.clone()),
+      None => self.affixes.apply(ident, "Blocking", "blocking type"),
+    }
+  }
+
+  /// Parses the value of a single argument, returning false if `key` is unknown
+  fn parse_arg(&mut self, key: &syn::Ident, input: ParseStream) -> syn::Result<bool> {
+    match key.to_string().as_str() {
+      "target" => {
+        input.parse::<Token![=]>()?;
+        set(&mut self.target, key, input.parse()?)?;
+      },
+      "prefix" => {
+        input.parse::<Token![=]>()?;
+        set(&mut self.affixes.prefix, key, input.parse()?)?;
+      },
+      "suffix" => {
+        input.parse::<Token![=]>()?;
+        set(&mut self.affixes.suffix, key, input.parse()?)?;
+      },
+      _ => return Ok(false),
+    }
+    Ok(true)
+  }
+
+  /// Makes sure the name was not set in two ways
+  fn validate(&self) -> syn::Result<()> {
+    // a target already names the blocking type so it can not be combined with a pattern
+    if let (Some(_), Some(affix)) = (&self.target, self.affixes.first()) {
+      let msg = "`prefix` and `suffix` can not be used with `target`";
+      return Err(syn::Error::new(affix.span(), msg));
+    }
+    Ok(())
+  }
+}
+
+impl Parse for TypeName {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut name = TypeName::default();
+    let expected = "`target`, `prefix` or `suffix`";
+    parse_list(input, expected, |key, input| name.parse_arg(key, input))?;
+    name.validate()?;
+    Ok(name)
+  }
+}
+
+/// The arguments that can be passed to `#[ut::newtype]`
+#[derive(Default)]
+pub(crate) struct NewtypeArgs {
+  /// The name of the blocking type
+  pub name: TypeName,
+  /// Whether the blocking type holds the async value in an `Arc`
+  pub arc: Option<syn::Ident>,
+}
+
+impl Parse for NewtypeArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut newtype = NewtypeArgs::default();
+    let expected = "`arc`, `target`, `prefix` or `suffix`";
+    parse_list(input, expected, |key, input| match key.to_string().as_str() {
+      "arc" => {
+        set(&mut newtype.arc, key, key.clone())?;
+        Ok(true)
+      },
+      _ => newtype.name.parse_arg(key, input),
+    })?;
+    newtype.name.validate()?;
+    Ok(newtype)
+  }
+}
+
+/// The options set on an item of a cloned impl with `#[ut(..)]`
+#[derive(Default)]
+pub(crate) struct ItemOptions {
+  /// Whether the item is left out of the blocking impl
+  pub skip: Option<syn::Ident>,
+  /// Whether the item is copied into the blocking impl as is
+  pub copy: Option<syn::Ident>,
+  /// The name of the blocking version of an async method
+  pub rename: Option<syn::Ident>,
+  /// How long the blocking version of an async method waits before giving up
+  pub timeout: Option<Timeout>,
+  /// The attributes only added to the blocking version of an async method
+  pub blocking_attrs: Vec<syn::Attribute>,
+  /// The first option that was set which only applies to async methods
+  pub method_only: Option<syn::Ident>,
+}
+
+impl ItemOptions {
+  /// Takes the `#[ut(..)]` options off of an item
+  pub fn take(item: &mut syn::ImplItem) -> syn::Result<Self> {
+    match item {
+      syn::ImplItem::Const(item) => ItemOptions::take_attrs(&mut item.attrs),
+      syn::ImplItem::Method(item) => ItemOptions::take_attrs(&mut item.attrs),
+      syn::ImplItem::Type(item) => ItemOptions::take_attrs(&mut item.attrs),
+      syn::ImplItem::Macro(item) => ItemOptions::take_attrs(&mut item.attrs),
+      _ => Ok(ItemOptions::default()),
+    }
+  }
+
+  /// Takes the `#[ut(..)]` options off of an item of a trait
+  pub fn take_trait(item: &mut syn::TraitItem) -> syn::Result<Self> {
+    match item {
+      syn::TraitItem::Const(item) => ItemOptions::take_attrs(&mut item.attrs),
+      syn::TraitItem::Method(item) => ItemOptions::take_attrs(&mut item.attrs),
+      syn::TraitItem::Type(item) => ItemOptions::take_attrs(&mut item.attrs),
+      syn::TraitItem::Macro(item) => ItemOptions::take_attrs(&mut item.attrs),
+      _ => Ok(ItemOptions::default()),
+    }
+  }
+
+  /// Takes the `#[ut(..)]` options out of the attributes of an item
+  fn take_attrs(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Self> {
+    let (options, others) = attrs.drain(..).partition(|attr: &syn::Attribute| attr.path.is_ident("ut"));
+    *attrs = others;
+    let mut item_options = ItemOptions::default();
+    for option in options {
+      option.parse_args_with(|input: ParseStream| item_options.parse_args(input))?;
+    }
+    // only one of skip and copy can be picked
+    if let (Some(_), Some(copy)) = (&item_options.skip, &item_options.copy) {
+      let msg = "`copy` can not be used with `skip`";
+      return Err(syn::Error::new(copy.span(), msg));
+    }
+    // skipped methods have no blocking version to change
+    if let (Some(_), Some(key)) = (&item_options.skip, &item_options.method_only) {
+      let msg = format!("`{}` can not be used with `skip`", key);
+      return Err(syn::Error::new(key.span(), msg));
+    }
+    Ok(item_options)
+  }
+
+  /// Parses the options in a single `#[ut(..)]` attribute
+  fn parse_args(&mut self, input: ParseStream) -> syn::Result<()> {
+    let expected = "`skip`, `copy`, `rename`, `timeout` or `blocking_attr`";
+    parse_list(input, expected, |key, input| {
+      match key.to_string().as_str() {
+        "skip" => set(&mut self.skip, key, key.clone())?,
+        "copy" => set(&mut self.copy, key, key.clone())?,
+        "rename" => {
+          input.parse::<Token![=]>()?;
+          let name: syn::LitStr = input.parse()?;
+          let ident = match syn::parse_str::<syn::Ident>(&name.value()) {
+            Ok(_) => syn::Ident::new(&name.value(), name.span()),
+            Err(_) => {
+              let msg = format!("`{}` is not a valid name for the blocking method", name.value());
+              return Err(syn::Error::new_spanned(name, msg));
+            },
+          };
+          set(&mut self.rename, key, ident)?;
+        },
+        "timeout" => {
+          input.parse::<Token![=]>()?;
+          set(&mut self.timeout, key, input.parse()?)?;
+        },
+        "blocking_attr" => {
+          // every attribute in the list is added as its own #[..]
+          let content;
+          syn::parenthesized!(content in input);
+          let metas = content.parse_terminated::<syn::Meta, Token![,]>(syn::Meta::parse)?;
+          self.blocking_attrs.extend(metas.into_iter().map(|meta| syn::parse_quote!(#[#meta])));
+        },
+        _ => return Ok(false),
+      }
+      // remember the option in case it ends up on an item it does not apply to
+      if !matches!(key.to_string().as_str(), "skip" | "copy") && self.method_only.is_none() {
+        self.method_only = Some(key.clone());
+      }
+      Ok(true)
+    })
+  }
+}
+
+/// Parses a comma separated list of arguments
+///
+/// `parse_arg` is given the name of each argument and parses its value,
+/// returning false for arguments that are not one of the `expected` ones.
+fn parse_list<F>(input: ParseStream, expected: &str, mut parse_arg: F) -> syn::Result<()>
+where
+  F: FnMut(&syn::Ident, ParseStream) -> syn::Result<bool>,
+{
+  while !input.is_empty() {
+    // get the name of this argument
+    let key: syn::Ident = input.parse()?;
+    if !parse_arg(&key, input)? {
+      let msg = format!("unknown ut argument `{}`, expected {}", key, expected);
+      return Err(syn::Error::new(key.span(), msg));
+    }
+    // arguments are separated by commas
+    if !input.is_empty() {
+      input.parse::<Token![,]>()?;
+    }
+  }
+  Ok(())
+}
+
+/// Sets an argument making sure it was not already set
+fn set<T>(slot: &mut Option<T>, key: &syn::Ident, value: T) -> syn::Result<()> {
+  if slot.is_some() {
+    return Err(syn::Error::new(key.span(), format!("`{}` was already set", key)));
+  }
+  *slot = Some(value);
+  Ok(())
+}
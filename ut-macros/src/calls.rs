@@ -0,0 +1,173 @@
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use std::collections::{HashMap, HashSet};
+use syn::visit_mut::{self, VisitMut};
+
+/// Gets the name of the hidden async copy of a method on a blocking type
+///
+/// Copies of the methods of a trait impl are named after the trait too so they
+/// do not clash with the copies of other impls for the same type.
+fn hidden(scope: Option<&syn::Path>, name: &syn::Ident) -> syn::Ident {
+  let mut prefix = String::from("__ut_async_");
+  if let Some(scope) = scope {
+    // keep generic arguments apart as a type can implement Foo<A> and Foo<B>
+    // while keeping the name snake case
+    let mut last = '_';
+    for c in quote::quote!(#scope).to_string().chars() {
+      let c = if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' };
+      if c != '_' || last != '_' {
+        prefix.push(c);
+      }
+      last = c;
+    }
+    if last != '_' {
+      prefix.push('_');
+    }
+  }
+  syn::Ident::new(&format!("{}{}", prefix, name), name.span())
+}
+
+/// Points calls to the async methods of an impl at their hidden async copies
+///
+/// Copied method bodies still call their siblings like `self.bar(x).await`
+/// while `bar` is synchronous on the blocking type. Calls on `self` and through
+/// `Self::bar` are rewritten, including the ones passed to macros. Bodies
+/// expanded by `#[async_trait]` call them on `__self` instead.
+pub(crate) struct Rewrite {
+  /// The hidden copy of every async method by the name of the method
+  names: HashMap<syn::Ident, syn::Ident>,
+  /// The hidden copies that calls were pointed at
+  used: HashSet<syn::Ident>,
+}
+
+impl Rewrite {
+  /// Rewrites calls to the methods called `names` of an impl of the trait `scope` if any
+  pub fn new<'a>(scope: Option<&syn::Path>, names: impl IntoIterator<Item = &'a syn::Ident>) -> Self {
+    let names = names.into_iter().map(|name| (name.clone(), hidden(scope, name))).collect();
+    Rewrite { names, used: HashSet::new() }
+  }
+
+  /// Gets the name of the hidden copy of the async method called `name`
+  pub fn hidden(&self, name: &syn::Ident) -> syn::Ident {
+    self.names[name].clone()
+  }
+
+  /// Checks if any rewritten call was pointed at the hidden copy called `hidden`
+  pub fn uses(&self, hidden: &syn::Ident) -> bool {
+    self.used.contains(hidden)
+  }
+
+  /// Gets the hidden copy of the method called `name` if it is one of ours
+  fn rename(&mut self, name: &syn::Ident) -> Option<syn::Ident> {
+    let hidden = self.names.get(name)?.clone();
+    self.used.insert(hidden.clone());
+    Some(hidden)
+  }
+
+  /// Rewrites the tokens passed to a macro
+  ///
+  /// Macros are not parsed so this looks for `self.bar(` and `Self::bar`. Only
+  /// calls on self are renamed as `self.bar` may be a field of the same name.
+  fn tokens(&mut self, tokens: TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for index in 0..tokens.len() {
+      let after_self = follows_self(&tokens[..index], &tokens[index + 1..]);
+      let renamed = match &tokens[index] {
+        TokenTree::Ident(ident) if after_self => match self.rename(ident) {
+          Some(hidden) => TokenTree::Ident(hidden),
+          None => continue,
+        },
+        TokenTree::Group(group) => {
+          let mut renamed = Group::new(group.delimiter(), self.tokens(group.stream()));
+          renamed.set_span(group.span());
+          TokenTree::Group(renamed)
+        },
+        _ => continue,
+      };
+      tokens[index] = renamed;
+    }
+    tokens.into_iter().collect()
+  }
+}
+
+impl VisitMut for Rewrite {
+  fn visit_expr_method_call_mut(&mut self, call: &mut syn::ExprMethodCall) {
+    visit_mut::visit_expr_method_call_mut(self, call);
+    // only calls on self are calls to our methods
+    let on_self = match &*call.receiver {
+      syn::Expr::Path(path) => path.qself.is_none() && (path.path.is_ident("self") || path.path.is_ident("__self")),
+      _ => false,
+    };
+    if !on_self {
+      return;
+    }
+    if let Some(hidden) = self.rename(&call.method) {
+      call.method = hidden;
+    }
+  }
+
+  fn visit_expr_path_mut(&mut self, path: &mut syn::ExprPath) {
+    visit_mut::visit_expr_path_mut(self, path);
+    // only look at paths like Self::bar
+    let segments = &mut path.path.segments;
+    if path.qself.is_some() || segments.len() != 2 || segments[0].ident != "Self" {
+      return;
+    }
+    if let Some(hidden) = self.rename(&segments[1].ident) {
+      segments[1].ident = hidden;
+    }
+  }
+
+  fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+    mac.tokens = self.tokens(std::mem::take(&mut mac.tokens));
+  }
+
+  fn visit_item_mut(&mut self, _: &mut syn::Item) {
+    // items nested in a body have a self of their own
+  }
+}
+
+/// Points the associated types of a trait impl used by hidden copies at the trait
+///
+/// Hidden copies live in an inherent impl where `Self::Error` is ambiguous, so
+/// the types of the impl they were copied from become `<Self as Api>::Error`.
+pub(crate) struct Qualify<'a> {
+  /// The trait the blocking type implements
+  pub path: &'a syn::Path,
+  /// The associated types set in the impl
+  pub types: HashSet<&'a syn::Ident>,
+}
+
+impl VisitMut for Qualify<'_> {
+  fn visit_type_path_mut(&mut self, ty: &mut syn::TypePath) {
+    visit_mut::visit_type_path_mut(self, ty);
+    let segments = &ty.path.segments;
+    if ty.qself.is_some() || segments.len() != 2 || segments[0].ident != "Self" {
+      return;
+    }
+    if !self.types.contains(&segments[1].ident) {
+      return;
+    }
+    let (path, segment) = (self.path, &segments[1]);
+    *ty = syn::parse_quote!(<Self as #path>::#segment);
+  }
+
+  fn visit_item_mut(&mut self, _: &mut syn::Item) {
+    // items nested in a body have a self of their own
+  }
+}
+
+/// Checks if tokens end in `self.` followed by a call or in `Self::`
+fn follows_self(tokens: &[TokenTree], rest: &[TokenTree]) -> bool {
+  let is = |token: &TokenTree, text: &str| token.to_string() == text;
+  // names after self. are only methods when they are called
+  let called = match rest {
+    [TokenTree::Group(group), ..] => group.delimiter() == Delimiter::Parenthesis,
+    [first, second, third, ..] => is(first, ":") && is(second, ":") && is(third, "<"),
+    _ => false,
+  };
+  match tokens {
+    [.., this, dot] if is(dot, ".") => called && (is(this, "self") || is(this, "__self")),
+    [.., this, first, second] if is(first, ":") && is(second, ":") => is(this, "Self"),
+    _ => false,
+  }
+}
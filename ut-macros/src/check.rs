@@ -0,0 +1,115 @@
+use crate::args::ItemOptions;
+
+/// Makes sure a function is async so it can be made synchronous
+pub(crate) fn is_async(sig: &syn::Signature, attr: &str) -> syn::Result<()> {
+  if sig.asyncness.is_some() {
+    return Ok(());
+  }
+  let msg = format!(
+    "`#[ut::{}]` only works on async functions\n\nhelp: add `async` before `fn`",
+    attr,
+  );
+  Err(syn::Error::new(sig.fn_token.span, msg))
+}
+
+/// Makes sure a typed receiver refers to `Self` so it also fits the blocking type
+pub(crate) fn receiver(sig: &syn::Signature) -> syn::Result<()> {
+  let receiver = match sig.inputs.first() {
+    Some(syn::FnArg::Typed(arg)) => arg,
+    _ => return Ok(()),
+  };
+  // typed receivers look like any other argument that happens to be named self
+  match &*receiver.pat {
+    syn::Pat::Ident(pat) if pat.ident == "self" => (),
+    _ => return Ok(()),
+  }
+  if mentions_self(&receiver.ty) {
+    return Ok(());
+  }
+  let msg = "unsupported receiver, the blocking method can not take this type as self\n\n\
+             help: write the receiver in terms of `Self` like `self: Box<Self>`";
+  Err(syn::Error::new_spanned(&receiver.ty, msg))
+}
+
+/// Checks if a type refers to `Self` anywhere inside of it
+fn mentions_self(ty: &syn::Type) -> bool {
+  fn walk(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+      proc_macro2::TokenTree::Ident(ident) => ident == "Self",
+      proc_macro2::TokenTree::Group(group) => walk(group.stream()),
+      _ => false,
+    })
+  }
+  walk(quote::quote!(#ty))
+}
+
+/// Gets the path of the type an impl is for
+pub(crate) fn self_path(imp: &syn::ItemImpl) -> syn::Result<&syn::TypePath> {
+  if let Some((Some(bang), _, _)) = &imp.trait_ {
+    let msg = "`#[ut::clone_impl]` does not support negative impls";
+    return Err(syn::Error::new_spanned(bang, msg));
+  }
+  match &*imp.self_ty {
+    syn::Type::Path(path) if path.qself.is_none() && !path.path.segments.is_empty() => Ok(path),
+    _ => {
+      let msg = "`#[ut::clone_impl]` only supports impls for a named type\n\n\
+                 help: write the type by its path like `impl Fooers` or `impl<T> api::Fooers<T>`";
+      Err(syn::Error::new_spanned(&imp.self_ty, msg))
+    },
+  }
+}
+
+/// Makes sure every item in an impl can be cloned
+///
+/// Async methods get a blocking version on the blocking type and `copy` only
+/// makes sense for the items that do not.
+pub(crate) fn items(items: &[syn::ImplItem], options: &[ItemOptions]) -> syn::Result<()> {
+  let sigs = items.iter().map(|item| match item {
+    syn::ImplItem::Method(method) if method.sig.asyncness.is_some() => Some(&method.sig),
+    _ => None,
+  });
+  combine(sigs.zip(options).map(|(sig, options)| item(sig, options)))
+}
+
+/// Makes sure every item in a trait can be cloned
+pub(crate) fn trait_items(items: &[syn::TraitItem], options: &[ItemOptions]) -> syn::Result<()> {
+  let sigs = items.iter().map(|item| match item {
+    syn::TraitItem::Method(method) if method.sig.asyncness.is_some() => Some(&method.sig),
+    _ => None,
+  });
+  combine(sigs.zip(options).map(|(sig, options)| item(sig, options)))
+}
+
+/// Makes sure the options of an item fit it, `sig` being set for async methods
+fn item(sig: Option<&syn::Signature>, options: &ItemOptions) -> syn::Result<()> {
+  match (sig, &options.copy) {
+    (Some(_), Some(copy)) => {
+      let msg = "async methods can not be copied as is, they always get a blocking version\n\n\
+                 help: remove `copy` or leave the method out with `skip`";
+      Err(syn::Error::new(copy.span(), msg))
+    },
+    (Some(sig), None) => receiver(sig),
+    // only async methods have a blocking version to rename or block differently
+    (None, _) => match &options.method_only {
+      Some(key) => {
+        let msg = format!("`{}` only applies to async methods", key);
+        Err(syn::Error::new(key.span(), msg))
+      },
+      None => Ok(()),
+    },
+  }
+}
+
+/// Combines the errors of every checked item
+///
+/// Every bad item is reported at once instead of one per build.
+pub(crate) fn combine(checked: impl Iterator<Item = syn::Result<()>>) -> syn::Result<()> {
+  let mut errors: Option<syn::Error> = None;
+  for err in checked.filter_map(Result::err) {
+    match &mut errors {
+      Some(errors) => errors.combine(err),
+      None => errors = Some(err),
+    }
+  }
+  errors.map_or(Ok(()), Err)
+}
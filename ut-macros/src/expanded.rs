@@ -0,0 +1,213 @@
+use syn::visit_mut::{self, VisitMut};
+
+/// Turns a method expanded by `#[async_trait]` back into an async method
+///
+/// `#[async_trait]` runs before us when it is put above our attribute and
+/// turns `async fn get(&self) -> T` into a method returning a boxed future:
+///
+/// ```text
+/// fn get<'life0, 'async_trait>(&'life0 self) -> Pin<Box<dyn Future<Output = T> + Send + 'async_trait>>
+/// where
+///   'life0: 'async_trait,
+///   Self: 'async_trait;
+/// ```
+///
+/// The signature is put back the way it was written and the lints async-trait
+/// allowed are dropped. Returns false for methods that were not expanded.
+pub(crate) fn async_trait(sig: &mut syn::Signature, attrs: &mut Vec<syn::Attribute>) -> bool {
+  // only methods with the lifetime of the boxed future were expanded
+  if !sig.generics.lifetimes().any(|param| param.lifetime.ident == "async_trait") {
+    return false;
+  }
+  let output = match &sig.output {
+    syn::ReturnType::Type(_, ty) => future_output(ty),
+    syn::ReturnType::Default => None,
+  };
+  let output = match output {
+    Some(output) => output.clone(),
+    None => return false,
+  };
+  sig.asyncness = Some(Default::default());
+  sig.output = match output {
+    syn::Type::Tuple(tuple) if tuple.elems.is_empty() => syn::ReturnType::Default,
+    output => syn::parse_quote!(-> #output),
+  };
+  // drop the lifetimes that were added
+  let params = std::mem::take(&mut sig.generics.params);
+  sig.generics.params = params.into_iter().filter(|param| match param {
+    syn::GenericParam::Lifetime(param) => !is_added(&param.lifetime),
+    _ => true,
+  }).collect();
+  if sig.generics.params.is_empty() {
+    sig.generics.lt_token = None;
+    sig.generics.gt_token = None;
+  }
+  // move the bounds of our generics back out of the where clause
+  if let Some(where_clause) = &mut sig.generics.where_clause {
+    let predicates = std::mem::take(&mut where_clause.predicates);
+    where_clause.predicates = predicates.into_iter().filter_map(predicate).collect();
+    if where_clause.predicates.is_empty() {
+      sig.generics.where_clause = None;
+    }
+  }
+  // elide the lifetimes given to references and impl Trait arguments
+  for input in &mut sig.inputs {
+    Elide.visit_fn_arg_mut(input);
+  }
+  // the lints are only allowed for the expanded method
+  attrs.retain(|attr| {
+    !(attr.path.is_ident("allow") && attr.tokens.to_string().contains("type_repetition_in_bounds"))
+  });
+  true
+}
+
+/// Gets the bound `#[async_trait]` is going to put on Self for a default method
+///
+/// `#[async_trait]` runs after us when it is put below our attribute, and the
+/// future it boxes for a default body holds self. So the type has to be `Sync`
+/// for methods taking `&self` and `Send` for the others, unless the trait is
+/// `?Send`. Returns None for attributes that are not `#[async_trait]`.
+pub(crate) fn default_bound(sig: &syn::Signature, attr: &syn::Attribute) -> Option<syn::WherePredicate> {
+  match attr.path.segments.last() {
+    Some(segment) if segment.ident == "async_trait" => (),
+    _ => return None,
+  }
+  if attr.tokens.to_string().contains("? Send") {
+    return None;
+  }
+  let bounds = match sig.inputs.first()? {
+    syn::FnArg::Receiver(receiver) => match &receiver.reference {
+      Some(_) if receiver.mutability.is_none() => quote::quote!(::core::marker::Sync),
+      _ => quote::quote!(::core::marker::Send),
+    },
+    syn::FnArg::Typed(arg) => {
+      match &*arg.pat {
+        syn::Pat::Ident(pat) if pat.ident == "self" => (),
+        _ => return None,
+      }
+      match &*arg.ty {
+        syn::Type::Reference(ty) if ty.mutability.is_none() => quote::quote!(::core::marker::Sync),
+        _ if generic_arg(&arg.ty, "Arc").is_some() => {
+          quote::quote!(::core::marker::Sync + ::core::marker::Send)
+        },
+        _ => quote::quote!(::core::marker::Send),
+      }
+    },
+  };
+  Some(syn::parse_quote!(Self: #bounds))
+}
+
+/// Gets `T` out of a type like `Pin<Box<dyn Future<Output = T> + Send>>`
+fn future_output(ty: &syn::Type) -> Option<&syn::Type> {
+  let boxed = generic_arg(ty, "Pin")?;
+  let object = match generic_arg(boxed, "Box")? {
+    syn::Type::TraitObject(object) => object,
+    _ => return None,
+  };
+  // find the future among the bounds of the trait object
+  object.bounds.iter().find_map(|bound| {
+    let segment = match bound {
+      syn::TypeParamBound::Trait(bound) => bound.path.segments.last()?,
+      syn::TypeParamBound::Lifetime(_) => return None,
+    };
+    match &segment.arguments {
+      syn::PathArguments::AngleBracketed(args) if segment.ident == "Future" => {
+        args.args.iter().find_map(|arg| match arg {
+          syn::GenericArgument::Binding(binding) if binding.ident == "Output" => Some(&binding.ty),
+          _ => None,
+        })
+      },
+      _ => None,
+    }
+  })
+}
+
+/// Gets the only generic argument of a type whose path ends in `name`
+fn generic_arg<'a>(ty: &'a syn::Type, name: &str) -> Option<&'a syn::Type> {
+  let segment = match ty {
+    syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+    _ => return None,
+  };
+  match &segment.arguments {
+    syn::PathArguments::AngleBracketed(args) if segment.ident == name && args.args.len() == 1 => {
+      match &args.args[0] {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+      }
+    },
+    _ => None,
+  }
+}
+
+/// Checks if a lifetime is one async-trait adds like `'async_trait` or `'life0`
+fn is_added(lifetime: &syn::Lifetime) -> bool {
+  let name = lifetime.ident.to_string();
+  match name.strip_prefix("life") {
+    Some(index) => !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()),
+    None => name == "async_trait",
+  }
+}
+
+/// Removes the bounds async-trait added to a where predicate, dropping it if none are left
+fn predicate(predicate: syn::WherePredicate) -> Option<syn::WherePredicate> {
+  match predicate {
+    syn::WherePredicate::Lifetime(mut predicate) => {
+      if is_added(&predicate.lifetime) {
+        return None;
+      }
+      predicate.bounds = predicate.bounds.into_iter().filter(|bound| !is_added(bound)).collect();
+      match predicate.bounds.is_empty() {
+        true => None,
+        false => Some(syn::WherePredicate::Lifetime(predicate)),
+      }
+    },
+    syn::WherePredicate::Type(mut predicate) => {
+      // default bodies keep the Sync or Send bound on Self the boxed future needs
+      predicate.bounds = predicate.bounds.into_iter().filter(|bound| match bound {
+        syn::TypeParamBound::Lifetime(lifetime) => !is_added(lifetime),
+        syn::TypeParamBound::Trait(_) => true,
+      }).collect();
+      match predicate.bounds.is_empty() {
+        true => None,
+        false => Some(syn::WherePredicate::Type(predicate)),
+      }
+    },
+    predicate => Some(predicate),
+  }
+}
+
+/// Elides the lifetimes async-trait gave to the arguments of a method
+struct Elide;
+
+impl VisitMut for Elide {
+  fn visit_receiver_mut(&mut self, receiver: &mut syn::Receiver) {
+    if let Some((_, lifetime)) = &mut receiver.reference {
+      if lifetime.as_ref().is_some_and(is_added) {
+        *lifetime = None;
+      }
+    }
+  }
+
+  fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+    if reference.lifetime.as_ref().is_some_and(is_added) {
+      reference.lifetime = None;
+    }
+    visit_mut::visit_type_reference_mut(self, reference);
+  }
+
+  fn visit_type_impl_trait_mut(&mut self, ty: &mut syn::TypeImplTrait) {
+    let bounds = std::mem::take(&mut ty.bounds);
+    ty.bounds = bounds.into_iter().filter(|bound| match bound {
+      syn::TypeParamBound::Lifetime(lifetime) => !is_added(lifetime),
+      syn::TypeParamBound::Trait(_) => true,
+    }).collect();
+    visit_mut::visit_type_impl_trait_mut(self, ty);
+  }
+
+  fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+    // lifetimes left in paths like Cow<'life0, str> can only be elided with '_
+    if is_added(lifetime) {
+      *lifetime = syn::Lifetime::new("'_", lifetime.span());
+    }
+  }
+}
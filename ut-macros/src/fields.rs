@@ -0,0 +1,109 @@
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::Token;
+
+use crate::check;
+
+/// The derives that are copied onto blocking structs
+pub(crate) const DERIVES: &[&str] = &["Debug", "Clone", "Copy", "Default", "PartialEq", "Eq", "PartialOrd", "Ord", "Hash"];
+
+/// What a blocking struct does with a field of the async one
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Mode {
+  /// Use the blocking version of the field's type
+  Map,
+  /// Use the field's type as is
+  Keep,
+  /// Leave the field out
+  Skip,
+}
+
+/// Takes the `#[ut(..)]` options off of every field and gets what to do with them
+pub(crate) fn modes(fields: &mut syn::Fields) -> syn::Result<Vec<Mode>> {
+  let checked: Vec<_> = fields
+    .iter_mut()
+    .map(|field| {
+      let (options, attrs) = field.attrs.drain(..).partition(|attr| attr.path.is_ident("ut"));
+      field.attrs = attrs;
+      mode(&options)
+    })
+    .collect();
+  // report every bad field at once instead of one per build
+  let mut modes = Vec::new();
+  check::combine(checked.into_iter().map(|mode| mode.map(|mode| modes.push(mode))))?;
+  Ok(modes)
+}
+
+/// Gets what to do with a field from its `#[ut(..)]` options
+fn mode(options: &[syn::Attribute]) -> syn::Result<Mode> {
+  let mut mode: Option<(Mode, syn::Ident)> = None;
+  for option in options {
+    let idents = option.parse_args_with(Punctuated::<syn::Ident, Token![,]>::parse_terminated)?;
+    for ident in idents {
+      let found = match ident.to_string().as_str() {
+        "skip" => Mode::Skip,
+        "keep" => Mode::Keep,
+        _ => {
+          let msg = format!("unknown ut field option `{}`, expected `skip` or `keep`", ident);
+          return Err(syn::Error::new(ident.span(), msg));
+        },
+      };
+      if let Some((_, first)) = &mode {
+        let msg = format!("`{}` can not be used with `{}`, a field is either skipped or kept", ident, first);
+        return Err(syn::Error::new(ident.span(), msg));
+      }
+      mode = Some((found, ident));
+    }
+  }
+  Ok(mode.map_or(Mode::Map, |(mode, _)| mode))
+}
+
+/// Turns the fields of an async struct into the fields of its blocking version
+pub(crate) fn map(fields: &mut syn::Fields, modes: &[Mode]) {
+  let fields = match fields {
+    syn::Fields::Named(fields) => &mut fields.named,
+    syn::Fields::Unnamed(fields) => &mut fields.unnamed,
+    syn::Fields::Unit => return,
+  };
+  for (mut field, mode) in std::mem::take(fields).into_iter().zip(modes) {
+    match mode {
+      Mode::Map => {
+        // point errors about types without a blocking version at the field
+        let ty = &field.ty;
+        field.ty = syn::parse_quote_spanned!(ty.span()=> <#ty as ::ut::Blocking>::Blocking);
+      },
+      Mode::Keep => (),
+      Mode::Skip => continue,
+    }
+    // other attributes belong to derives that are not copied
+    field.attrs.retain(|attr| attr.path.is_ident("doc") || attr.path.is_ident("cfg"));
+    fields.push(field);
+  }
+}
+
+/// Gets the attributes of an async struct that also apply to its blocking version
+///
+/// Only cfgs and the standard derives in `allowed` are copied.
+pub(crate) fn attrs(attrs: &[syn::Attribute], allowed: &[&str]) -> Vec<syn::Attribute> {
+  let mut kept = Vec::new();
+  for attr in attrs {
+    if attr.path.is_ident("cfg") {
+      kept.push(attr.clone());
+      continue;
+    }
+    if !attr.path.is_ident("derive") {
+      continue;
+    }
+    let derives = match attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated) {
+      Ok(derives) => derives,
+      Err(_) => continue,
+    };
+    let derives: Vec<_> = derives.into_iter().filter(|path| {
+      path.segments.last().is_some_and(|segment| allowed.iter().any(|derive| segment.ident == derive))
+    }).collect();
+    if !derives.is_empty() {
+      kept.push(syn::parse_quote!(#[derive(#(#derives),*)]));
+    }
+  }
+  kept
+}
@@ -0,0 +1,288 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+
+use crate::args::{self, Args};
+use crate::types::Convert;
+
+/// The arguments a blocking function passes on to its async original
+pub(crate) struct Forward {
+  /// Whether the function takes self
+  receiver: bool,
+  /// Where self is taken so errors about moving it point there
+  span: Span,
+  /// How self passes on the value in one of its fields like `&` or `&mut`
+  borrow: TokenStream,
+  /// The names of the arguments after self
+  args: Vec<syn::Ident>,
+  /// The generic arguments to call the async function with if they can be named
+  turbofish: Option<TokenStream>,
+}
+
+/// Binds every argument of a blocking signature to a name it can be forwarded with
+///
+/// Arguments bound to patterns like `(a, b): (u8, u8)` get a name of their own
+/// and `mut` is dropped as the blocking function only moves its arguments.
+pub(crate) fn bind(sig: &mut syn::Signature) -> Forward {
+  let mut receiver = false;
+  let mut span = Span::call_site();
+  let mut borrow = TokenStream::new();
+  let mut args = Vec::new();
+  for (index, input) in sig.inputs.iter_mut().enumerate() {
+    match input {
+      syn::FnArg::Receiver(arg) => {
+        receiver = true;
+        span = arg.self_token.span;
+        borrow = match &arg.reference {
+          Some(_) => {
+            let mutability = &arg.mutability;
+            quote!(&#mutability)
+          },
+          // only by value receivers can be mut
+          None => {
+            arg.mutability = None;
+            TokenStream::new()
+          },
+        };
+      },
+      syn::FnArg::Typed(arg) => {
+        let ident = bound(index, &arg.pat);
+        // typed receivers are named self like any other argument
+        if ident == "self" {
+          receiver = true;
+          span = ident.span();
+          if let syn::Type::Reference(ty) = &*arg.ty {
+            let mutability = &ty.mutability;
+            borrow = quote!(&#mutability);
+          }
+        } else {
+          args.push(ident.clone());
+        }
+        *arg.pat = syn::parse_quote!(#ident);
+      },
+    }
+  }
+  // impl Trait arguments can not be named so their functions are called without a turbofish
+  let params: Vec<_> = sig.generics.params.iter().filter_map(|param| match param {
+    syn::GenericParam::Type(param) => Some(&param.ident),
+    syn::GenericParam::Const(param) => Some(&param.ident),
+    syn::GenericParam::Lifetime(_) => None,
+  }).collect();
+  let inputs = &sig.inputs;
+  let turbofish = match params.is_empty() || mentions_impl(quote!(#inputs)) {
+    true => None,
+    false => Some(quote!(::<#(#params),*>)),
+  };
+  Forward { receiver, span, borrow, args, turbofish }
+}
+
+/// Gets the name an argument at `index` bound to `pat` is forwarded with
+fn bound(index: usize, pat: &syn::Pat) -> syn::Ident {
+  match pat {
+    syn::Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat.ident.clone(),
+    _ => syn::Ident::new(&format!("__ut_arg{}", index), proc_macro2::Span::call_site()),
+  }
+}
+
+impl Forward {
+  /// Gets the future of the async function `name` written as `sig` and `block`
+  ///
+  /// Functions without self may or may not be in an impl so calling them by
+  /// name could find another function. Their block is run in place instead
+  /// with the arguments bound to the patterns it expects.
+  pub fn call(&self, name: &syn::Ident, sig: &syn::Signature, block: &syn::Block) -> TokenStream {
+    let Forward { args, turbofish, .. } = self;
+    if self.receiver {
+      return quote!(Self::#name #turbofish(self, #(#args),*));
+    }
+    let rebind = sig.inputs.iter().enumerate().filter_map(|(index, input)| {
+      let pat = match input {
+        syn::FnArg::Typed(arg) => &arg.pat,
+        syn::FnArg::Receiver(_) => return None,
+      };
+      // arguments that were only renamed or made immutable get their pattern back
+      match &**pat {
+        syn::Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() && ident.mutability.is_none() => {
+          None
+        },
+        _ => {
+          let ident = bound(index, pat);
+          Some(quote!(let #pat = #ident;))
+        },
+      }
+    });
+    let stmts = &block.stmts;
+    quote!(async move { #(#rebind)* #(#stmts)* })
+  }
+
+  /// Gets the future returned by the method `name` of the same type
+  pub fn method(&self, name: &syn::Ident) -> TokenStream {
+    self.qualified(&quote!(Self), name)
+  }
+
+  /// Gets the value returned by the method `name` found through `path`
+  ///
+  /// Paths like `<T as Transport>` pick the trait the method comes from.
+  pub fn qualified(&self, path: &TokenStream, name: &syn::Ident) -> TokenStream {
+    let Forward { args, turbofish, .. } = self;
+    match self.receiver {
+      true => quote!(#path::#name #turbofish(self, #(#args),*)),
+      false => quote!(#path::#name #turbofish(#(#args),*)),
+    }
+  }
+
+  /// Gets the future returned by the async method `name` of `async_ty`
+  ///
+  /// Methods taking self are called on the `field` holding the async value.
+  pub fn delegate(&self, field: &syn::Member, async_ty: &syn::TypePath, name: &syn::Ident) -> TokenStream {
+    let Forward { args, turbofish, .. } = self;
+    match self.receiver {
+      // async values held in an Arc can not be moved out of so point at self
+      true => {
+        let mut field = field.clone();
+        match &mut field {
+          syn::Member::Named(ident) => ident.set_span(self.span),
+          syn::Member::Unnamed(index) => index.span = self.span,
+        }
+        quote_spanned!(self.span=> self.#field.#name #turbofish(#(#args),*))
+      },
+      false => quote!(<#async_ty>::#name #turbofish(#(#args),*)),
+    }
+  }
+
+  /// Gets the value returned by the method `name` found through `path` for a field
+  ///
+  /// Methods taking self are passed the `field` holding the async value, as
+  /// calling them like methods could pick the blocking trait instead.
+  pub fn delegate_qualified(&self, field: &syn::Member, path: &TokenStream, name: &syn::Ident) -> TokenStream {
+    let Forward { borrow, args, turbofish, .. } = self;
+    match self.receiver {
+      true => quote!(#path::#name #turbofish(#borrow self.#field, #(#args),*)),
+      false => quote!(#path::#name #turbofish(#(#args),*)),
+    }
+  }
+}
+
+/// Where a blocking method finds the async method it calls
+pub(crate) enum Target<'a> {
+  /// The async value held in a field of the blocking type
+  Field(&'a syn::Member, &'a syn::TypePath),
+  /// A hidden async copy of the method on the blocking type itself
+  Copy(syn::Ident),
+  /// The async value held in a field, called through a path like `<T as Api>`
+  Qualified(&'a syn::Member, TokenStream),
+}
+
+/// Builds a blocking method that calls its async original
+///
+/// The blocking type gets an async method doing the same if blocking is not enabled.
+pub(crate) fn method(
+  args: &Args,
+  method: &syn::ImplItemMethod,
+  name: syn::Ident,
+  target: Target,
+  convert: Option<Convert>,
+) -> TokenStream {
+  // get attributes (docstrings/examples) for our method
+  let attrs = &method.attrs;
+  // get visibility of method
+  let vis = &method.vis;
+  // get the blocking signature and the future of the async method we are calling
+  let (sync_sig, future) = blocking(args, method, name.clone(), target, convert);
+  let mut async_sig = method.sig.clone();
+  async_sig.ident = name;
+  bind(&mut async_sig);
+  // block on the async method
+  let sync_body = args.blocking_body(future.clone(), args.fallible.is_some());
+  // track the manifest our defaults came from in both versions
+  let track = args.track();
+  // get the cfg predicate that enables blocking
+  let cfg = args.cfg();
+  quote!{
+    // iterate and add all of our attributes
+    #(#attrs)*
+    // block on our executor if blocking is enabled
+    #[cfg(#cfg)]
+    #vis #sync_sig {
+      #track
+      #sync_body
+    }
+
+    // iterate and add all of our attributes
+    #(#attrs)*
+    // await the async method if blocking is not enabled
+    #[cfg(not(#cfg))]
+    #vis #async_sig {
+      #track
+      #future.await
+    }
+  }
+}
+
+/// Builds a blocking method of a trait impl that calls its async original
+///
+/// Blocking traits only exist when blocking is enabled so no async version is
+/// added and the caller puts the whole impl behind the cfg.
+pub(crate) fn trait_method(
+  args: &Args,
+  method: &syn::ImplItemMethod,
+  name: syn::Ident,
+  target: Target,
+  convert: Option<Convert>,
+) -> TokenStream {
+  // get attributes (docstrings/examples) for our method
+  let attrs = &method.attrs;
+  // get the blocking signature and the future of the async method we are calling
+  let (sync_sig, future) = blocking(args, method, name, target, convert);
+  // block on the async method
+  let sync_body = args.blocking_body(future, args.fallible.is_some());
+  // track the manifest our defaults came from
+  let track = args.track();
+  quote!{
+    #(#attrs)*
+    #sync_sig {
+      #track
+      #sync_body
+    }
+  }
+}
+
+/// Gets the signature of a blocking method and the future it blocks on
+///
+/// The future converts the value of the async method with `convert` if its
+/// return type was mapped to a blocking type.
+fn blocking(
+  args: &Args,
+  method: &syn::ImplItemMethod,
+  name: syn::Ident,
+  target: Target,
+  convert: Option<Convert>,
+) -> (syn::Signature, TokenStream) {
+  // fallible methods return an error instead of panicking
+  let fallible = args.fallible.is_some();
+  // get the signature of our method with arguments that can be passed on
+  let mut sync_sig = args::blocking_sig(&method.sig, name, fallible);
+  let forward = bind(&mut sync_sig);
+  // get the future of the async method we are calling
+  let future = match target {
+    Target::Field(field, async_ty) => forward.delegate(field, async_ty, &method.sig.ident),
+    Target::Copy(hidden) => forward.method(&hidden),
+    Target::Qualified(field, path) => forward.delegate_qualified(field, &path, &method.sig.ident),
+  };
+  let future = match convert {
+    Some(convert) => {
+      let value = convert.apply(quote!(#future.await));
+      quote!(async move { #value })
+    },
+    None => future,
+  };
+  (sync_sig, future)
+}
+
+/// Checks if tokens contain an `impl Trait` type anywhere inside of them
+fn mentions_impl(tokens: TokenStream) -> bool {
+  tokens.into_iter().any(|token| match token {
+    proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+    proc_macro2::TokenTree::Group(group) => mentions_impl(group.stream()),
+    _ => false,
+  })
+}
@@ -0,0 +1,1800 @@
+//! Procedural macros for [ut](https://docs.rs/ut)
+//!
+//! These macros should be used through the ut crate as the code they
+//! generate refers to runtime support that lives there.
+
+use quote::{quote, quote_spanned};
+use proc_macro::TokenStream;
+use syn::visit_mut::VisitMut;
+
+mod args;
+mod calls;
+mod check;
+mod expanded;
+mod fields;
+mod forward;
+mod metadata;
+mod types;
+
+/// Wraps an async function in order to make it synchronous
+///
+/// # Examples
+///
+/// ```
+/// #[ut::wrap]
+/// async fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// let out = foo("sync");
+/// assert_eq!(out, "I am sync now".to_owned())
+/// ```
+///
+/// The rest of the signature is kept as is, including where clauses and
+/// qualifiers like `unsafe`:
+///
+/// ```
+/// #[ut::wrap]
+/// async unsafe fn first<T>(items: *const T) -> T
+/// where
+///   T: Copy,
+/// {
+///  *items
+/// }
+///
+/// let items = [1, 2, 3];
+/// let out = unsafe { first(items.as_ptr()) };
+/// assert_eq!(out, 1)
+/// ```
+///
+/// # Arguments
+///
+/// - `executor = path`: blocks with `path` instead of the shared runtime. This
+///   must be a function like `fn block_on<F: Future>(future: F) -> F::Output`.
+///   Timeouts still apply as the future is wrapped before it is passed in.
+/// - `background`: blocks on a runtime owned by a background thread. This
+///   works inside of other runtimes but the future has to be `Send`.
+/// - `fallible`: makes the synchronous function return
+///   `Result<T, ut::BlockingError>` instead of panicking when the future can
+///   not be run.
+/// - `timeout = "30s"`: gives up on blocking after a number of ms, s, m or h.
+///   This overrides the default set with `ut::set_default_timeout`.
+/// - `cfg = "feature = \"blocking\""`: only blocks when this cfg predicate is
+///   true instead of when the sync feature is enabled.
+///
+/// Functions stay async when the cfg predicate is false:
+///
+/// ```
+/// #[ut::wrap(cfg = "not(feature = \"sync\")")]
+/// async fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// let out = ut::block_on(foo("async"));
+/// assert_eq!(out, "I am async now".to_owned())
+/// ```
+///
+/// # Errors
+///
+/// Only async functions can be wrapped:
+///
+/// ```compile_fail
+/// #[ut::wrap]
+/// fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn wrap(meta: TokenStream, input: TokenStream) -> TokenStream {
+  // parse the arguments passed to our macro
+  let meta = syn::parse_macro_input!(meta as args::Args);
+  // parse the input stream into our async function
+  let func = syn::parse_macro_input!(input as syn::ItemFn);
+  // make sure we were given an async function
+  if let Err(err) = check::is_async(&func.sig, "wrap") {
+    return err.to_compile_error().into();
+  }
+  // get attributes (docstrings/examples) for our function
+  let attrs = &func.attrs;
+  // get visibility of function
+  let vis = &func.vis;
+  // get the signature of our function
+  let sig = &func.sig;
+  // get the block of instrutions that are going to be called
+  let block = &func.block;
+  // fallible functions return an error instead of panicking
+  let fallible = meta.fallible.is_some();
+  // get the same signature without async
+  let sync_sig = args::blocking_sig(sig, sig.ident.clone(), fallible);
+  // block on our async block
+  let sync_body = meta.blocking_body(quote!(async move #block), fallible);
+  // track the manifest our defaults came from in both versions
+  let track = meta.track();
+  let async_block = match &track {
+    Some(track) => {
+      let stmts = &block.stmts;
+      quote!({ #track #(#stmts)* })
+    },
+    None => quote!(#block),
+  };
+  // get the cfg predicate that enables blocking
+  let cfg = meta.cfg();
+  // cast back to a token stream
+  let output = quote!{
+    // iterate and add all of our attributes
+    #(#attrs)*
+    // block on our executor if blocking is enabled
+    #[cfg(#cfg)]
+    #vis #sync_sig {
+      #track
+      #sync_body
+    }
+
+    // iterate and add all of our attributes
+    #(#attrs)*
+    // leave the function async if blocking is not enabled
+    #[cfg(not(#cfg))]
+    #vis #sig #async_block
+  };
+  output.into()
+}
+
+/// Clones an async function in order to make it also synchronous
+///
+/// This will add _blocking to the name of the function to clone. Clones of
+/// methods taking self block on a call to the async method instead of copying
+/// its body. When blocking is not enabled the clone is an async function
+/// awaiting it instead.
+///
+/// # Examples
+///
+/// ```
+/// #[ut::clone]
+/// async fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// let out = foo_blocking("sync");
+/// assert_eq!(out, "I am sync now".to_owned())
+/// ```
+///
+/// Arguments bound to patterns are passed on as a whole:
+///
+/// ```
+/// #[ut::clone]
+/// async fn sum((a, b): (u8, u8), mut c: u8) -> u8 {
+///  c += 1;
+///  a + b + c
+/// }
+///
+/// assert_eq!(sum_blocking((1, 2), 3), 7)
+/// ```
+///
+/// Functions that do not take self copy their body instead, as calling them by
+/// name could find a free function of the same name when they are in an impl:
+///
+/// ```
+/// #[derive(Debug, PartialEq)]
+/// pub struct S(u8);
+///
+/// async fn new(x: u8) -> S {
+///   S(x + 100)
+/// }
+///
+/// impl S {
+///   #[ut::clone(fallible)]
+///   pub async fn new(x: u8) -> S {
+///     S(x)
+///   }
+/// }
+///
+/// assert_eq!(S::new_blocking(1), S(1));
+/// assert_eq!(S::new_try_blocking(1).unwrap(), S(1));
+/// assert_eq!(ut::block_on(new(1)), S(101))
+/// ```
+///
+/// Clones stay async when the cfg predicate is false:
+///
+/// ```
+/// #[ut::clone(cfg = "not(feature = \"sync\")")]
+/// async fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// let out = ut::block_on(foo_blocking("async"));
+/// assert_eq!(out, "I am async now".to_owned())
+/// ```
+///
+/// # Arguments
+///
+/// - `executor = path`: blocks with `path` instead of the shared runtime. This
+///   must be a function like `fn block_on<F: Future>(future: F) -> F::Output`.
+///   Timeouts still apply as the future is wrapped before it is passed in.
+/// - `background`: blocks on a runtime owned by a background thread. This
+///   works inside of other runtimes but the future has to be `Send`.
+/// - `fallible`: also adds a clone ending in _try_blocking that returns
+///   `Result<T, ut::BlockingError>` instead of panicking when the future can
+///   not be run. Renamed clones get a fallible clone named like them but
+///   starting with `try_`.
+/// - `timeout = "30s"`: gives up on blocking after a number of ms, s, m or h.
+///   This overrides the default set with `ut::set_default_timeout`.
+/// - `cfg = "feature = \"blocking\""`: only blocks when this cfg predicate is
+///   true instead of when the sync feature is enabled.
+/// - `name = "foo_sync"`: names the clone `foo_sync` instead of adding
+///   _blocking to the name of the function.
+/// - `prefix = "blocking_"`, `suffix = "_sync"`: names the clone by adding a
+///   prefix and/or a suffix to the name of the function.
+///
+/// ```
+/// #[ut::clone(suffix = "_sync")]
+/// async fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// #[ut::clone(name = "get_bar", fallible)]
+/// async fn bar(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// assert_eq!(foo_sync("sync"), "I am sync now".to_owned());
+/// assert_eq!(try_get_bar("sync").unwrap(), "I am sync now".to_owned())
+/// ```
+///
+/// ```
+/// #[ut::clone(fallible)]
+/// async fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// let out = foo_try_blocking("sync");
+/// assert_eq!(out.unwrap(), "I am sync now".to_owned())
+/// ```
+///
+/// ```
+/// mod rt {
+///   # use std::future::Future;
+///   # use std::sync::Arc;
+///   # use std::task::{Context, Poll, Wake};
+///   # use std::thread::{self, Thread};
+///   # struct ThreadWaker(Thread);
+///   # impl Wake for ThreadWaker {
+///   #   fn wake(self: Arc<Self>) {
+///   #     self.0.unpark();
+///   #   }
+///   # }
+///   // an in house executor that polls futures on the current thread
+///   pub fn block_on<F: Future>(future: F) -> F::Output {
+///     let mut future = Box::pin(future);
+///     let waker = Arc::new(ThreadWaker(thread::current())).into();
+///     let mut cx = Context::from_waker(&waker);
+///     loop {
+///       match future.as_mut().poll(&mut cx) {
+///         Poll::Ready(output) => return output,
+///         Poll::Pending => thread::park(),
+///       }
+///     }
+///   }
+/// }
+///
+/// #[ut::clone(executor = rt::block_on)]
+/// async fn foo(input: &str) -> String {
+///  format!("I am {} now", input)
+/// }
+///
+/// let out = foo_blocking("sync");
+/// assert_eq!(out, "I am sync now".to_owned())
+/// ```
+#[proc_macro_attribute]
+pub fn clone(meta: TokenStream, input: TokenStream) -> TokenStream {
+  // parse the arguments passed to our macro
+  let meta = syn::parse_macro_input!(meta as args::CloneArgs);
+  // get the arguments used to block
+  let args = &meta.args;
+  // parse the input stream into our async function
+  let func = syn::parse_macro_input!(input as syn::ItemFn);
+  // make sure we were given an async function
+  if let Err(err) = check::is_async(&func.sig, "clone") {
+    return err.to_compile_error().into();
+  }
+  // get attributes (docstrings/examples) for our function
+  let attrs = &func.attrs;
+  // get visibility of function
+  let vis = &func.vis;
+  // get the signature of our function
+  let sig = &func.sig;
+  // get the name of our function
+  let name = &sig.ident;
+  // get the signature of our cloned function
+  let sync_name = match meta.sync_ident(name) {
+    Ok(sync_name) => sync_name,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  let mut sync_sig = args::blocking_sig(sig, sync_name.clone(), false);
+  // bind the arguments so they can be passed on to our async function
+  let forward = forward::bind(&mut sync_sig);
+  let mut async_sig = sig.clone();
+  async_sig.ident = sync_name;
+  forward::bind(&mut async_sig);
+  // get the block of instrutions that are going to be called
+  let block = &func.block;
+  // get the future of the async function we are cloning
+  let future = forward.call(name, sig, block);
+  // block on our async function
+  let sync_body = args.blocking_body(future.clone(), false);
+  // track the manifest our defaults came from in every version
+  let track = args.track();
+  let async_block = match &track {
+    Some(track) => {
+      let stmts = &block.stmts;
+      quote!({ #track #(#stmts)* })
+    },
+    None => quote!(#block),
+  };
+  // get the cfg predicate that enables blocking
+  let cfg = args.cfg();
+  // add a fallible clone that ends in _try_blocking if requested
+  let try_clone = match &args.fallible {
+    Some(_) => {
+      let try_name = match meta.try_ident(name) {
+        Ok(try_name) => try_name,
+        Err(err) => return err.to_compile_error().into(),
+      };
+      let mut try_sig = args::blocking_sig(sig, try_name, true);
+      forward::bind(&mut try_sig);
+      let try_body = args.blocking_body(future.clone(), true);
+      quote!{
+        // iterate and add all of our attributes
+        #(#attrs)*
+        // block on our executor if blocking is enabled
+        #[cfg(#cfg)]
+        #vis #try_sig {
+          #track
+          #try_body
+        }
+      }
+    },
+    None => quote!(),
+  };
+  // cast back to a token stream
+  let output = quote!{
+    // iterate and add all of our attributes
+    #(#attrs)*
+    #vis #sig #async_block
+    
+    // iterate and add all of our attributes
+    #(#attrs)*
+    // block on our executor if blocking is enabled
+    #[cfg(#cfg)]
+    #vis #sync_sig {
+      #track
+      #sync_body
+    }
+
+    // iterate and add all of our attributes
+    #(#attrs)*
+    // await the async function if blocking is not enabled
+    #[cfg(not(#cfg))]
+    #vis #async_sig {
+      #track
+      #future.await
+    }
+
+    #try_clone
+  };
+  output.into()
+}
+
+
+/// Clones an group of async functions in an impl to a new sub structure
+///
+/// This is useful when you want to support both async and sync functions
+/// in a struct implementation.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Default)]
+/// pub struct Example {
+///   pub fooers: Fooers,
+/// }
+///
+/// #[derive(Default)]
+/// pub struct ExampleBlocking {
+///   pub fooers: FooersBlocking,
+/// }
+///
+/// #[derive(Default)]
+/// pub struct Fooers;
+///
+/// #[derive(Default)]
+/// pub struct FooersBlocking;
+///
+/// #[ut::clone_impl]
+/// impl Fooers {
+///   pub async fn foo(&self, input: &str) -> String {
+///     format!("I am {} now", input)
+///   }
+/// }
+///
+/// let out = ExampleBlocking::default().fooers.foo("sync");
+/// assert_eq!(out, "I am sync now".to_owned())
+/// ```
+///
+/// The blocking methods run copies of the async methods that stay async, so
+/// methods can still await each other through `self` or `Self`:
+///
+/// ```
+/// pub struct Counter {
+///   count: u32,
+/// }
+///
+/// pub struct CounterBlocking {
+///   count: u32,
+/// }
+///
+/// #[ut::clone_impl]
+/// impl Counter {
+///   pub async fn count(&self) -> u32 {
+///     self.count
+///   }
+///
+///   pub async fn double(&self) -> u32 {
+///     self.count().await + Self::zero().await + self.count().await
+///   }
+///
+///   pub async fn zero() -> u32 {
+///     0
+///   }
+/// }
+///
+/// let counter = CounterBlocking { count: 2 };
+/// assert_eq!(counter.double(), 4)
+/// ```
+///
+/// Generic impls keep their generics and where clauses on the blocking impl:
+///
+/// ```
+/// pub trait Transport {
+///   fn name(&self) -> &str;
+/// }
+///
+/// pub struct Http;
+///
+/// impl Transport for Http {
+///   fn name(&self) -> &str {
+///     "http"
+///   }
+/// }
+///
+/// pub struct Client<T> {
+///   transport: T,
+/// }
+///
+/// pub struct ClientBlocking<T> {
+///   transport: T,
+/// }
+///
+/// #[ut::clone_impl]
+/// impl<T: Transport> Client<T> where T: Send {
+///   pub async fn name(&self) -> String {
+///     self.transport.name().to_owned()
+///   }
+/// }
+///
+/// let client = ClientBlocking { transport: Http };
+/// assert_eq!(client.name(), "http".to_owned())
+/// ```
+///
+/// Types named by their path clone into the blocking type in the same module,
+/// unless another module is picked with `module`:
+///
+/// ```
+/// mod api {
+///   pub struct Users;
+///
+///   pub struct UsersBlocking;
+/// }
+///
+/// mod blocking {
+///   pub struct UsersBlocking;
+/// }
+///
+/// #[ut::clone_impl]
+/// impl api::Users {
+///   pub async fn name(&self) -> String {
+///     "api".to_owned()
+///   }
+/// }
+///
+/// #[ut::clone_impl(module = blocking)]
+/// impl api::Users {
+///   pub async fn id(&self) -> u32 {
+///     1
+///   }
+/// }
+///
+/// assert_eq!(api::UsersBlocking.name(), "api".to_owned());
+/// assert_eq!(blocking::UsersBlocking.id(), 1)
+/// ```
+///
+/// The blocking type can also be named explicitly or through a pattern:
+///
+/// ```
+/// pub struct AsyncClient;
+///
+/// pub struct SyncClient;
+///
+/// pub struct Users;
+///
+/// pub struct SyncUsers;
+///
+/// #[ut::clone_impl(target = SyncClient)]
+/// impl AsyncClient {
+///   pub async fn ping(&self) -> bool {
+///     true
+///   }
+/// }
+///
+/// #[ut::clone_impl(prefix = "Sync")]
+/// impl Users {
+///   pub async fn count(&self) -> usize {
+///     2
+///   }
+/// }
+///
+/// #[ut::clone_impl(target = SyncClient, method_suffix = "_sync")]
+/// impl AsyncClient {
+///   pub async fn version(&self) -> u32 {
+///     3
+///   }
+/// }
+///
+/// assert!(SyncClient.ping());
+/// assert_eq!(SyncClient.version_sync(), 3);
+/// assert_eq!(SyncUsers.count(), 2)
+/// ```
+///
+/// Copying methods doubles the code that has to be compiled for them. Blocking
+/// types that hold the async value can call its methods instead:
+///
+/// ```
+/// pub struct Users {
+///   name: String,
+/// }
+///
+/// pub struct UsersBlocking {
+///   inner: Users,
+/// }
+///
+/// #[ut::clone_impl(delegate = inner)]
+/// impl Users {
+///   pub async fn new(name: &str) -> Users {
+///     Users { name: name.to_owned() }
+///   }
+///
+///   pub async fn greet(&self, greeting: &str) -> String {
+///     format!("{} {}", greeting, self.name)
+///   }
+/// }
+///
+/// let users = UsersBlocking { inner: UsersBlocking::new("sam") };
+/// assert_eq!(users.greet("hi"), "hi sam".to_owned())
+/// ```
+///
+/// Methods returning other async types can return their blocking versions
+/// instead with `map`, which converts them with `Into`. Delegating methods
+/// returning `Self` convert the async value into the blocking type the same way:
+///
+/// ```
+/// #[ut::newtype]
+/// pub struct Users {
+///   count: usize,
+/// }
+///
+/// #[ut::clone_impl(delegate = inner)]
+/// impl Users {
+///   pub async fn count(&self) -> usize {
+///     self.count
+///   }
+/// }
+///
+/// #[ut::newtype]
+/// pub struct Client {
+///   token: Option<String>,
+/// }
+///
+/// #[ut::clone_impl(delegate = inner, map(Users))]
+/// impl Client {
+///   pub async fn new() -> Self {
+///     Client { token: None }
+///   }
+///
+///   pub async fn with_token(self, token: &str) -> Self {
+///     Client { token: Some(token.to_owned()) }
+///   }
+///
+///   pub async fn users(&self) -> Result<Users, String> {
+///     match &self.token {
+///       Some(_) => Ok(Users { count: 2 }),
+///       None => Err("no token".to_owned()),
+///     }
+///   }
+/// }
+///
+/// let client = ClientBlocking::new().with_token("secret");
+/// let users: UsersBlocking = client.users().unwrap();
+/// assert_eq!(users.count(), 2)
+/// ```
+///
+/// Only async methods get a blocking version. Consts, types and sync methods
+/// are copied onto the blocking type as is, and any item can be kept on the
+/// async type alone with `#[ut(skip)]`:
+///
+/// ```
+/// pub struct Pages {
+///   size: u32,
+/// }
+///
+/// pub struct PagesBlocking {
+///   size: u32,
+/// }
+///
+/// #[ut::clone_impl]
+/// impl Pages {
+///   pub const MAX: u32 = 100;
+///
+///   fn clamp(&self) -> u32 {
+///     self.size.min(Self::MAX)
+///   }
+///
+///   pub async fn size(&self) -> u32 {
+///     self.clamp()
+///   }
+///
+///   #[ut(skip)]
+///   pub async fn stream(&self) -> Vec<u32> {
+///     vec![self.size().await]
+///   }
+/// }
+///
+/// let pages = PagesBlocking { size: 250 };
+/// assert_eq!(pages.size(), PagesBlocking::MAX)
+/// ```
+///
+/// Calls to other async methods are still found inside of macros, while fields
+/// named like a method are left alone:
+///
+/// ```
+/// pub struct Counter {
+///   count: u32,
+/// }
+///
+/// pub struct CounterBlocking {
+///   count: u32,
+/// }
+///
+/// #[ut::clone_impl]
+/// impl Counter {
+///   pub async fn count(&self) -> u32 {
+///     self.count
+///   }
+///
+///   pub async fn describe(&self) -> String {
+///     assert!(self.count < 10, "{} is too many", self.count);
+///     format!("{} of {}", self.count().await, self.count)
+///   }
+/// }
+///
+/// let counter = CounterBlocking { count: 2 };
+/// assert_eq!(counter.describe(), "2 of 2".to_owned())
+/// ```
+///
+/// Blocking methods can be renamed, timed out and given attributes one by one:
+///
+/// ```
+/// pub struct Jobs;
+///
+/// pub struct JobsBlocking;
+///
+/// #[ut::clone_impl(method_suffix = "_sync")]
+/// impl Jobs {
+///   #[ut(rename = "count")]
+///   pub async fn len(&self) -> usize {
+///     3
+///   }
+///
+///   #[ut(timeout = "30s", blocking_attr(must_use = "the job may have failed"))]
+///   pub async fn run(&self) -> bool {
+///     true
+///   }
+/// }
+///
+/// assert_eq!(JobsBlocking.count(), 3);
+/// assert!(JobsBlocking.run_sync())
+/// ```
+///
+/// Impls of a trait cloned with `#[ut::clone_trait]` implement its blocking
+/// trait for the blocking type. The blocking trait is found next to the async
+/// one unless another one is picked with `trait_target`:
+///
+/// ```
+/// mod api {
+///   #[ut::clone_trait]
+///   pub trait Api {
+///     type Error;
+///
+///     async fn get(&self, id: u32) -> Result<String, Self::Error>;
+///   }
+/// }
+///
+/// pub struct Http {
+///   url: String,
+/// }
+///
+/// pub struct HttpBlocking {
+///   url: String,
+/// }
+///
+/// #[ut::clone_impl]
+/// impl api::Api for Http {
+///   type Error = ();
+///
+///   async fn get(&self, id: u32) -> Result<String, Self::Error> {
+///     Ok(format!("{}/{}", self.url, id))
+///   }
+/// }
+///
+/// use api::ApiBlocking;
+///
+/// let http = HttpBlocking { url: "localhost".to_owned() };
+/// assert_eq!(http.get(1), Ok("localhost/1".to_owned()))
+/// ```
+///
+/// This also works with `#[async_trait]`, whether it is put above or below
+/// `#[ut::clone_impl]`. Methods of the same name from several impls for one
+/// type are kept apart:
+///
+/// ```
+/// #[ut::clone_trait]
+/// pub trait Left {
+///   async fn get(&self) -> u8;
+/// }
+///
+/// #[ut::clone_trait]
+/// pub trait Right {
+///   async fn get(&self) -> u8;
+/// }
+///
+/// pub struct Both;
+///
+/// pub struct BothBlocking;
+///
+/// #[ut::clone_impl]
+/// impl Left for Both {
+///   async fn get(&self) -> u8 {
+///     1
+///   }
+/// }
+///
+/// #[ut::clone_impl]
+/// impl Right for Both {
+///   async fn get(&self) -> u8 {
+///     2
+///   }
+/// }
+///
+/// #[ut::clone_impl]
+/// impl Both {
+///   pub async fn get(&self) -> u8 {
+///     3
+///   }
+///
+///   pub async fn sum(&self) -> u8 {
+///     self.get().await + 3
+///   }
+/// }
+///
+/// assert_eq!(LeftBlocking::get(&BothBlocking), 1);
+/// assert_eq!(RightBlocking::get(&BothBlocking), 2);
+/// assert_eq!(BothBlocking.sum(), 6)
+/// ```
+///
+/// Async methods with a default body have to be implemented by the impl, as
+/// the default body calls async methods the blocking type does not have. The
+/// async type still uses the default through the blocking trait:
+///
+/// ```
+/// use async_trait::async_trait;
+///
+/// mod api {
+///   use async_trait::async_trait;
+///
+///   #[async_trait]
+///   #[ut::clone_trait]
+///   pub trait Store {
+///     async fn get(&self, key: &str) -> Option<String>;
+///
+///     async fn get_or(&self, key: &str, default: String) -> String {
+///       self.get(key).await.unwrap_or(default)
+///     }
+///   }
+/// }
+///
+/// pub struct Memory;
+///
+/// pub struct MemoryBlocking;
+///
+/// #[ut::clone_impl]
+/// #[async_trait]
+/// impl api::Store for Memory {
+///   async fn get(&self, key: &str) -> Option<String> {
+///     Some(key.to_owned()).filter(|key| key != "b")
+///   }
+///
+///   async fn get_or(&self, key: &str, default: String) -> String {
+///     self.get(key).await.unwrap_or(default)
+///   }
+/// }
+///
+/// pub struct Empty;
+///
+/// #[async_trait]
+/// impl api::Store for Empty {
+///   async fn get(&self, _: &str) -> Option<String> {
+///     None
+///   }
+/// }
+///
+/// assert_eq!(api::StoreBlocking::get_or(&MemoryBlocking, "a", "c".to_owned()), "a");
+/// assert_eq!(api::StoreBlocking::get_or(&MemoryBlocking, "b", "c".to_owned()), "c");
+/// assert_eq!(api::StoreBlocking::get_or(&Empty, "a", "c".to_owned()), "c")
+/// ```
+///
+/// # Arguments
+///
+/// - `executor = path`: blocks with `path` instead of the shared runtime. This
+///   must be a function like `fn block_on<F: Future>(future: F) -> F::Output`.
+///   Timeouts still apply as the future is wrapped before it is passed in.
+/// - `background`: blocks on a runtime owned by a background thread. This
+///   works inside of other runtimes but the future has to be `Send`.
+/// - `fallible`: makes the blocking methods return
+///   `Result<T, ut::BlockingError>` instead of panicking when the future can
+///   not be run.
+/// - `timeout = "30s"`: gives up on blocking after a number of ms, s, m or h.
+///   This overrides the default set with `ut::set_default_timeout`.
+/// - `cfg = "feature = \"blocking\""`: only blocks when this cfg predicate is
+///   true instead of when the sync feature is enabled.
+/// - `module = path`: implements the blocking methods on the blocking type in
+///   the module at `path` instead of the one next to the async type.
+/// - `target = Name`: names the blocking type `Name` instead of adding
+///   `Blocking` to the name of the async type.
+/// - `prefix = "Sync"`, `suffix = "Blocking"`: names the blocking type by adding
+///   a prefix and/or a suffix to the name of the async type.
+/// - `method_prefix = "blocking_"`, `method_suffix = "_sync"`: names the
+///   blocking methods by adding a prefix and/or a suffix to the name of the
+///   async methods instead of keeping their names.
+/// - `delegate = field`: calls the async methods on the async value held in
+///   `field` of the blocking type instead of copying them.
+/// - `trait_target = path`: implements the blocking trait at `path` for trait
+///   impls instead of the async trait's name with Blocking added.
+/// - `map(Users, Orders = OrderList)`: makes blocking methods returning one of
+///   these types, on its own or in a `Result` or `Option`, return its blocking
+///   version. Types without one given are mapped through `ut::Blocking`.
+///
+/// Items can also be given options with `#[ut(..)]`:
+///
+/// - `skip`: keeps the item on the async type only.
+/// - `copy`: copies a const, type or sync method onto the blocking type as is.
+///   This is the default unless `delegate` is used, as those items usually
+///   work on the async value the blocking type holds. Trait impls always copy
+///   them, calling the sync methods of the async value when delegating.
+/// - `rename = "name"`: names the blocking version of an async method `name`
+///   instead of applying `method_prefix` and `method_suffix`.
+/// - `timeout = "30s"`: gives up on blocking in an async method after this
+///   long instead of the timeout of the impl.
+/// - `blocking_attr(..)`: adds attributes like `must_use` to the blocking
+///   version of an async method only.
+///
+/// # Errors
+///
+/// Only impls for a named type can be cloned and async methods can not be
+/// copied as is. Typed receivers must be written in terms of `Self`:
+///
+/// ```compile_fail
+/// pub struct Fooers;
+///
+/// pub struct FooersBlocking;
+///
+/// #[ut::clone_impl]
+/// impl Fooers {
+///   #[ut(copy)]
+///   pub async fn foo(&self, input: &str) -> String {
+///     format!("I am {} now", input)
+///   }
+/// }
+/// ```
+///
+/// Using a default body of the async trait through the blocking type fails to
+/// compile:
+///
+/// ```compile_fail
+/// #[ut::clone_trait]
+/// pub trait Counter {
+///   async fn count(&self) -> usize;
+///
+///   async fn double(&self) -> usize {
+///     self.count().await * 2
+///   }
+/// }
+///
+/// pub struct Three;
+///
+/// pub struct ThreeBlocking;
+///
+/// #[ut::clone_impl]
+/// impl Counter for Three {
+///   async fn count(&self) -> usize {
+///     3
+///   }
+/// }
+///
+/// assert_eq!(CounterBlocking::double(&ThreeBlocking), 6)
+/// ```
+#[proc_macro_attribute]
+pub fn clone_impl(meta: TokenStream, input: TokenStream) -> TokenStream {
+  // parse the arguments passed to our macro
+  let meta = syn::parse_macro_input!(meta as args::ImplArgs);
+  // get the arguments passed on to every blocking method
+  let args = &meta.args;
+  // parse the input stream into our impl
+  let mut imp = syn::parse_macro_input!(input as syn::ItemImpl);
+  // take our options off of every item
+  let options = match imp.items.iter_mut().map(args::ItemOptions::take).collect::<syn::Result<Vec<_>>>() {
+    Ok(options) => options,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  // get the self type for this impl
+  let self_ty = match check::self_path(&imp) {
+    Ok(self_ty) => self_ty,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  // look at the methods async-trait already expanded as they were written
+  // the original impl and the hidden copies are left expanded
+  let mut written = imp.items.clone();
+  for item in &mut written {
+    if let syn::ImplItem::Method(method) = item {
+      expanded::async_trait(&mut method.sig, &mut method.attrs);
+    }
+  }
+  // make sure every item can be cloned
+  if let Err(err) = check::items(&written, &options) {
+    return err.to_compile_error().into();
+  }
+  // build sync type by renaming the type while keeping its generic arguments
+  // self_path made sure the path has a segment to rename
+  let mut sync_ty = self_ty.clone();
+  let segment = sync_ty.path.segments.last_mut().unwrap();
+  segment.ident = match meta.sync_ident(&segment.ident) {
+    Ok(ident) => ident,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  // move the sync type into another module if one was given
+  if let Some(module) = &meta.module {
+    let segment = segment.clone();
+    sync_ty.path = module.clone();
+    sync_ty.path.segments.push(segment);
+  }
+  // trait impls implement the blocking trait instead
+  let traits = imp.trait_.as_ref().map(|(_, path, _)| (path, meta.sync_trait(path)));
+  // get the async methods that get a blocking version
+  let methods = written.iter().filter_map(|item| match item {
+    syn::ImplItem::Method(method) if method.sig.asyncness.is_some() => Some(method),
+    _ => None,
+  });
+  // copied methods call each other through their hidden async copies
+  let scope = traits.as_ref().map(|(async_trait, _)| *async_trait);
+  let mut rewrite = calls::Rewrite::new(scope, methods.map(|method| &method.sig.ident));
+  // the associated types of trait impls have to be named through the trait in hidden copies
+  let mut qualify = traits.as_ref().map(|(_, sync_trait)| calls::Qualify {
+    path: sync_trait,
+    types: imp.items.iter().filter_map(|item| match item {
+      syn::ImplItem::Type(ty) => Some(&ty.ident),
+      _ => None,
+    }).collect(),
+  });
+  // clone every item we were not told to skip
+  let mut items = Vec::new();
+  let mut copies = Vec::new();
+  let mut skipped = Vec::new();
+  for ((item, written), options) in imp.items.iter().zip(&written).zip(&options) {
+    let (original, method) = match (item, written) {
+      (syn::ImplItem::Method(original), syn::ImplItem::Method(method)) if method.sig.asyncness.is_some() => {
+        (original, method)
+      },
+      // sync methods of trait impls call the async value we hold through its trait
+      (syn::ImplItem::Method(method), _) if traits.is_some() && meta.delegate.is_some() => {
+        if options.skip.is_some() {
+          continue;
+        }
+        // delegate and traits were both checked above
+        let field = meta.delegate.as_ref().unwrap();
+        let async_trait = traits.as_ref().unwrap().0;
+        let mut sig = method.sig.clone();
+        let call = forward::bind(&mut sig).delegate_qualified(field, &quote!(<#self_ty as #async_trait>), &sig.ident);
+        let attrs = &method.attrs;
+        items.push(quote!(#(#attrs)* #sig { #call }));
+        continue;
+      },
+      // other items are copied as is unless they only work on the async value
+      // trait impls need every item the trait asks for
+      _ => {
+        let copied = options.copy.is_some()
+          || ((meta.delegate.is_none() || traits.is_some()) && options.skip.is_none());
+        if copied {
+          let mut item = item.clone();
+          rewrite.visit_impl_item_mut(&mut item);
+          items.push(quote!(#item));
+        }
+        continue;
+      },
+    };
+    // either call the async method on the value we hold or on a copy of it
+    let target = match (&meta.delegate, &traits) {
+      (Some(field), Some((async_trait, _))) => forward::Target::Qualified(field, quote!(<#self_ty as #async_trait>)),
+      (Some(field), None) => forward::Target::Field(field, self_ty),
+      // skipped methods only get a copy if the other copies call them
+      (None, _) if options.skip.is_some() => {
+        skipped.push(original);
+        continue;
+      },
+      (None, _) => {
+        // add a hidden async copy of the method to the blocking type
+        copies.push(hidden_copy(original, &mut rewrite, qualify.as_mut()));
+        forward::Target::Copy(rewrite.hidden(&method.sig.ident))
+      },
+    };
+    if options.skip.is_some() {
+      continue;
+    }
+    // methods renamed on their own ignore the method prefix and suffix
+    let sync_name = match &options.rename {
+      Some(rename) => rename.clone(),
+      None => match meta.method_ident(&method.sig.ident) {
+        Ok(sync_name) => sync_name,
+        Err(err) => return err.to_compile_error().into(),
+      },
+    };
+    // a timeout set on the method overrides the one for the whole impl
+    let mut method_args = args.clone();
+    if options.timeout.is_some() {
+      method_args.timeout = options.timeout.clone();
+    }
+    // add the attributes meant for the blocking version only
+    let mut method = method.clone();
+    method.attrs.extend(options.blocking_attrs.iter().cloned());
+    // return the blocking versions of mapped types, and of Self when we hold the async value
+    let map_self = meta.delegate.is_some();
+    let convert = match &meta.map {
+      Some(map) => map.output(&mut method.sig.output, map_self),
+      None => types::TypeMap::default().output(&mut method.sig.output, map_self),
+    };
+    items.push(match traits {
+      Some(_) => forward::trait_method(&method_args, &method, sync_name, target, convert),
+      None => forward::method(&method_args, &method, sync_name, target, convert),
+    });
+  }
+  // copy the skipped methods that are called until none of the ones left are
+  while let Some(index) = skipped.iter().position(|method| rewrite.uses(&rewrite.hidden(&method.sig.ident))) {
+    let method = skipped.remove(index);
+    copies.push(hidden_copy(method, &mut rewrite, qualify.as_mut()));
+  }
+  // get information on the generics to pass
+  let (impl_generics, _, where_clause) = imp.generics.split_for_impl();
+  // blocking traits only exist when blocking is enabled
+  let blocking = match &traits {
+    Some((_, sync_trait)) => {
+      let cfg = args.cfg();
+      let unsafety = &imp.unsafety;
+      let copies = match copies.is_empty() {
+        true => None,
+        false => Some(quote!{
+          impl #impl_generics #sync_ty #where_clause {
+            #(#copies)*
+          }
+        }),
+      };
+      // bring the methods of the blocking trait into scope for the copied bodies only
+      // as calls would be ambiguous wherever both traits are imported
+      let mut import = sync_trait.clone();
+      import.segments.iter_mut().for_each(|segment| segment.arguments = syn::PathArguments::None);
+      quote!{
+        #[cfg(#cfg)]
+        const _: () = {
+          use #import as _;
+
+          // Clone our async methods into the blocking trait but make them synchronous
+          #unsafety impl #impl_generics #sync_trait for #sync_ty #where_clause {
+            #(#items)*
+          }
+
+          #copies
+        };
+      }
+    },
+    None => quote!{
+      // Clone our async methods but make them synchronous
+      impl #impl_generics #sync_ty #where_clause {
+        #(#items)*
+        #(#copies)*
+      }
+    },
+  };
+  // cast back to a token stream
+  let output = quote!{
+    // add the original impl with its async methods
+    #imp
+
+    #blocking
+  };
+  output.into()
+}
+
+/// Builds the hidden async copy of a method that its blocking version calls
+///
+/// Copies of the methods of trait impls also get their associated types qualified.
+fn hidden_copy(
+  method: &syn::ImplItemMethod,
+  rewrite: &mut calls::Rewrite,
+  qualify: Option<&mut calls::Qualify>,
+) -> proc_macro2::TokenStream {
+  let mut copy = method.clone();
+  copy.sig.ident = rewrite.hidden(&method.sig.ident);
+  copy.vis = syn::Visibility::Inherited;
+  // hide the copy from docs and from lints about methods it only has for the blocking ones
+  copy.attrs.retain(|attr| !attr.path.is_ident("doc"));
+  copy.attrs.push(syn::parse_quote!(#[doc(hidden)]));
+  copy.attrs.push(syn::parse_quote!(#[allow(dead_code)]));
+  // point calls to other methods at their copies
+  rewrite.visit_block_mut(&mut copy.block);
+  if let Some(qualify) = qualify {
+    qualify.visit_impl_item_method_mut(&mut copy);
+  }
+  quote!(#copy)
+}
+
+
+/// Clones the async methods of a trait into a blocking trait
+///
+/// The blocking trait is named like the trait with Blocking added and has a
+/// synchronous version of every async method. It is implemented for every
+/// type implementing the async trait, so a transport only has to be written
+/// once. This works with native async methods as well as with `#[async_trait]`,
+/// which can go above or below this attribute.
+///
+/// # Examples
+///
+/// ```
+/// #[ut::clone_trait]
+/// pub trait Transport {
+///   type Error;
+///
+///   fn name(&self) -> &str;
+///
+///   async fn send(&self, body: &str) -> Result<usize, Self::Error>;
+/// }
+///
+/// pub struct Http;
+///
+/// impl Transport for Http {
+///   type Error = ();
+///
+///   fn name(&self) -> &str {
+///     "http"
+///   }
+///
+///   async fn send(&self, body: &str) -> Result<usize, ()> {
+///     Ok(body.len())
+///   }
+/// }
+///
+/// fn send<T: TransportBlocking>(transport: &T) -> Result<usize, T::Error> {
+///   transport.send("hello")
+/// }
+///
+/// assert_eq!(send(&Http), Ok(5));
+/// assert_eq!(TransportBlocking::name(&Http), "http")
+/// ```
+///
+/// Traits using `#[async_trait]` work the same way and keep working as trait
+/// objects:
+///
+/// ```
+/// use async_trait::async_trait;
+///
+/// #[async_trait]
+/// #[ut::clone_trait(method_suffix = "_sync")]
+/// pub trait Store {
+///   async fn get(&self, key: &str) -> Option<String>;
+/// }
+///
+/// pub struct Memory;
+///
+/// #[async_trait]
+/// impl Store for Memory {
+///   async fn get(&self, key: &str) -> Option<String> {
+///     Some(key.to_uppercase())
+///   }
+/// }
+///
+/// let store: Box<dyn Store> = Box::new(Memory);
+/// assert_eq!(store.get_sync("a"), Some("A".to_owned()))
+/// ```
+///
+/// With `opt_in` the blocking trait extends the async trait instead and only
+/// the types that ask for it implement it:
+///
+/// ```
+/// #[ut::clone_trait(opt_in)]
+/// pub trait Counter {
+///   async fn count(&self) -> usize;
+/// }
+///
+/// pub struct Three;
+///
+/// impl Counter for Three {
+///   async fn count(&self) -> usize {
+///     3
+///   }
+/// }
+///
+/// impl CounterBlocking for Three {}
+///
+/// assert_eq!(CounterBlocking::count(&Three), 3)
+/// ```
+///
+/// # Arguments
+///
+/// - `executor = path`: blocks with `path` instead of the shared runtime. This
+///   must be a function like `fn block_on<F: Future>(future: F) -> F::Output`.
+///   Timeouts still apply as the future is wrapped before it is passed in.
+/// - `background`: blocks on a runtime owned by a background thread. This
+///   works inside of other runtimes but the future has to be `Send`.
+/// - `fallible`: makes the blocking methods return
+///   `Result<T, ut::BlockingError>` instead of panicking when the future can
+///   not be run.
+/// - `timeout = "30s"`: gives up on blocking after a number of ms, s, m or h.
+///   This overrides the default set with `ut::set_default_timeout`.
+/// - `cfg = "feature = \"blocking\""`: only adds the blocking trait when this
+///   cfg predicate is true instead of when the sync feature is enabled.
+/// - `target = Name`: names the blocking trait `Name` instead of adding
+///   `Blocking` to the name of the async trait.
+/// - `prefix = "Sync"`, `suffix = "Blocking"`: names the blocking trait by
+///   adding a prefix and/or a suffix to the name of the async trait.
+/// - `method_prefix = "blocking_"`, `method_suffix = "_sync"`: names the
+///   blocking methods by adding a prefix and/or a suffix to the name of the
+///   async methods instead of keeping their names.
+/// - `opt_in`: makes the async trait a supertrait of the blocking trait, whose
+///   methods have default bodies, instead of implementing it for every type.
+///
+/// Consts, types and sync methods are copied into the blocking trait unless
+/// `opt_in` is used, and the blanket implementation takes them from the async
+/// trait. Items take the same `#[ut(..)]` options as in `#[ut::clone_impl]`
+/// except for `copy`.
+///
+/// Async methods with a default body keep it in the blocking trait for the
+/// types implementing the async trait, but impls cloned with
+/// `#[ut::clone_impl]` have to implement them as the blocking type can not run
+/// the default body.
+///
+/// Calling methods that both traits have is ambiguous where both of them are
+/// in scope, so only import the one that is used or rename the blocking
+/// methods with `method_prefix` or `method_suffix`.
+#[proc_macro_attribute]
+pub fn clone_trait(meta: TokenStream, input: TokenStream) -> TokenStream {
+  // parse the arguments passed to our macro
+  let meta = syn::parse_macro_input!(meta as args::TraitArgs);
+  // get the arguments passed on to every blocking method
+  let args = &meta.args;
+  // parse the input stream into our async trait
+  let mut item = syn::parse_macro_input!(input as syn::ItemTrait);
+  // take our options off of every item
+  let options = match item.items.iter_mut().map(args::ItemOptions::take_trait).collect::<syn::Result<Vec<_>>>() {
+    Ok(options) => options,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  // look at the methods async-trait already expanded as they were written
+  // the async trait itself is left expanded
+  let mut trait_items = item.items.clone();
+  for trait_item in &mut trait_items {
+    if let syn::TraitItem::Method(method) = trait_item {
+      expanded::async_trait(&mut method.sig, &mut method.attrs);
+    }
+  }
+  // make sure every item can be cloned
+  if let Err(err) = check::trait_items(&trait_items, &options) {
+    return err.to_compile_error().into();
+  }
+  // get visibility of trait
+  let vis = &item.vis;
+  // get the name of our trait
+  let name = &item.ident;
+  // get the name of our blocking trait
+  let sync_name = match meta.name.ident(name) {
+    Ok(sync_name) => sync_name,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  // get information on the generics to pass
+  let generics = &item.generics;
+  let (_, ty_generics, where_clause) = generics.split_for_impl();
+  let async_trait = quote!(#name #ty_generics);
+  // opt in traits call the async trait of the type itself
+  let opt_in = meta.opt_in.is_some();
+  let source = match opt_in {
+    true => quote!(<Self as #async_trait>),
+    false => quote!(<__Async as #async_trait>),
+  };
+  // get the cfg predicate that enables blocking
+  let cfg = args.cfg();
+  // track the manifest our defaults came from in every blocking method
+  let track = args.track();
+  // build the items of the blocking trait and of its blanket impl
+  let mut items = Vec::new();
+  let mut impl_items = Vec::new();
+  // unsized types can only implement methods that take self by reference
+  let mut unsized_ok = true;
+  for (trait_item, options) in trait_items.iter().zip(&options) {
+    if options.skip.is_some() {
+      continue;
+    }
+    let method = match trait_item {
+      syn::TraitItem::Method(method) if method.sig.asyncness.is_some() => method,
+      // the async trait already gives opt in traits its other items
+      _ if opt_in => continue,
+      syn::TraitItem::Const(item) => {
+        let (ident, ty) = (&item.ident, &item.ty);
+        items.push(quote!(#trait_item));
+        impl_items.push(quote!(const #ident: #ty = #source::#ident;));
+        continue;
+      },
+      syn::TraitItem::Type(item) => {
+        let ident = &item.ident;
+        let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+        items.push(quote!(#trait_item));
+        impl_items.push(quote!(type #ident #impl_generics = #source::#ident #ty_generics #where_clause;));
+        continue;
+      },
+      syn::TraitItem::Method(method) => {
+        let mut sig = method.sig.clone();
+        let forward = forward::bind(&mut sig);
+        let call = forward.qualified(&source, &method.sig.ident);
+        unsized_ok &= by_ref(&sig);
+        items.push(quote!(#trait_item));
+        impl_items.push(quote!(#sig { #call }));
+        continue;
+      },
+      // macros can not be implemented for every type so they are left out
+      _ => continue,
+    };
+    // methods renamed on their own ignore the method prefix and suffix
+    let sync_name = match &options.rename {
+      Some(rename) => rename.clone(),
+      None => match meta.method_ident(&method.sig.ident) {
+        Ok(sync_name) => sync_name,
+        Err(err) => return err.to_compile_error().into(),
+      },
+    };
+    // a timeout set on the method overrides the one for the whole trait
+    let mut method_args = args.clone();
+    if options.timeout.is_some() {
+      method_args.timeout = options.timeout.clone();
+    }
+    // get the signature of our blocking method with arguments that can be passed on
+    let fallible = method_args.fallible.is_some();
+    let mut sync_sig = args::blocking_sig(&method.sig, sync_name, fallible);
+    let forward = forward::bind(&mut sync_sig);
+    unsized_ok &= by_ref(&sync_sig);
+    // default bodies async-trait has yet to expand need the bound it adds to call them
+    if method.default.is_some() {
+      if let Some(bound) = item.attrs.iter().find_map(|attr| expanded::default_bound(&method.sig, attr)) {
+        sync_sig.generics.make_where_clause().predicates.push(bound);
+      }
+    }
+    // block on the async method of the trait
+    let sync_body = method_args.blocking_body(forward.qualified(&source, &method.sig.ident), fallible);
+    // get attributes (docstrings/examples) for our method
+    let attrs = method.attrs.iter().chain(&options.blocking_attrs);
+    match opt_in {
+      true => items.push(quote!{
+        #(#attrs)*
+        #sync_sig {
+          #track
+          #sync_body
+        }
+      }),
+      false => {
+        items.push(match &method.default {
+          Some(_) => {
+            // default bodies need the async methods of the type so they can not be
+            // cloned, instead types that use the default without implementing it
+            // fail to compile once the method is used
+            let msg = format!(
+              "`{}` has a default body in `{}` so it has to be implemented by impls cloned with `#[ut::clone_impl]`",
+              method.sig.ident,
+              name,
+            );
+            let span = method.sig.ident.span();
+            quote_spanned!{span=>
+              #(#attrs)*
+              #[allow(unused_variables)]
+              #sync_sig {
+                struct Missing<T: ?Sized>(::std::marker::PhantomData<T>);
+                impl<T: ?Sized> Missing<T> {
+                  const IMPLEMENTED: () = panic!(#msg);
+                }
+                let () = Missing::<Self>::IMPLEMENTED;
+                unreachable!()
+              }
+            }
+          },
+          None => quote!(#(#attrs)* #sync_sig;),
+        });
+        impl_items.push(quote!{
+          #sync_sig {
+            #track
+            #sync_body
+          }
+        });
+      },
+    }
+  }
+  // opt in traits extend the async trait while the others copy its supertraits
+  let mut supertraits = item.supertraits.clone();
+  if opt_in {
+    supertraits.push(syn::parse_quote!(#async_trait));
+  }
+  let colon = match supertraits.is_empty() {
+    true => None,
+    false => Some(quote!(:)),
+  };
+  // implement the blocking trait for every type implementing the async one
+  let unsafety = &item.unsafety;
+  let blanket = match opt_in {
+    true => None,
+    false => {
+      let mut blanket_generics = generics.clone();
+      let bounds = match unsized_ok {
+        true => quote!(#async_trait + ?Sized),
+        false => quote!(#async_trait),
+      };
+      blanket_generics.params.push(syn::parse_quote!(__Async: #bounds));
+      let (impl_generics, _, _) = blanket_generics.split_for_impl();
+      Some(quote!{
+        #[cfg(#cfg)]
+        #unsafety impl #impl_generics #sync_name #ty_generics for __Async #where_clause {
+          #(#impl_items)*
+        }
+      })
+    },
+  };
+  // only cfgs on the async trait also apply to the blocking one
+  let cfgs = item.attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+  let doc = format!("A blocking version of [`{}`]", name);
+  // cast back to a token stream
+  let output = quote!{
+    // add the original trait
+    #item
+
+    // add the blocking trait if blocking is enabled
+    #(#cfgs)*
+    #[doc = #doc]
+    #[cfg(#cfg)]
+    #vis #unsafety trait #sync_name #generics #colon #supertraits #where_clause {
+      #(#items)*
+    }
+
+    #blanket
+  };
+  output.into()
+}
+
+/// Checks if a method takes self by reference if it takes self at all
+fn by_ref(sig: &syn::Signature) -> bool {
+  match sig.inputs.first() {
+    Some(syn::FnArg::Receiver(receiver)) => receiver.reference.is_some(),
+    Some(syn::FnArg::Typed(arg)) => match (&*arg.pat, &*arg.ty) {
+      (syn::Pat::Ident(pat), ty) if pat.ident == "self" => matches!(ty, syn::Type::Reference(_)),
+      _ => false,
+    },
+    None => false,
+  }
+}
+
+/// Creates a blocking type that wraps an async struct
+///
+/// The blocking type is named like the struct with Blocking added and holds
+/// the async value in a field called `inner`, so the async methods can be
+/// cloned onto it with `#[ut::clone_impl(delegate = inner)]`. Both types can be
+/// converted into each other and are linked through `ut::Blocking`. `Debug` and
+/// `Clone` are derived for the blocking type when the struct derives them.
+///
+/// # Examples
+///
+/// ```
+/// #[ut::newtype]
+/// pub struct Users {
+///   name: String,
+/// }
+///
+/// #[ut::clone_impl(delegate = inner)]
+/// impl Users {
+///   pub async fn greet(&self, greeting: &str) -> String {
+///     format!("{} {}", greeting, self.name)
+///   }
+/// }
+///
+/// let users = UsersBlocking::from(Users { name: "sam".to_owned() });
+/// assert_eq!(users.greet("hi"), "hi sam".to_owned());
+/// let users: Users = users.into_async();
+/// ```
+///
+/// Async values shared between tasks can be held in an `Arc` instead, which
+/// also makes the blocking type cheap to clone:
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// #[ut::newtype(arc, prefix = "Sync")]
+/// pub struct Client {
+///   url: String,
+/// }
+///
+/// #[ut::clone_impl(delegate = inner, prefix = "Sync")]
+/// impl Client {
+///   pub async fn url(&self) -> String {
+///     self.url.clone()
+///   }
+///
+///   #[ut(skip)]
+///   pub async fn close(self) -> String {
+///     self.url
+///   }
+/// }
+///
+/// let client = Arc::new(Client { url: "localhost".to_owned() });
+/// let sync = SyncClient::from(client.clone());
+/// assert_eq!(sync.clone().url(), "localhost".to_owned());
+/// assert!(Arc::ptr_eq(&client, &sync.into_async()))
+/// ```
+///
+/// # Arguments
+///
+/// - `arc`: holds the async value in an `Arc` and implements `Clone` for the
+///   blocking type. The async value can not be moved out of the `Arc`, so
+///   methods taking self by value have to be skipped with `#[ut(skip)]` when
+///   cloning them with `delegate`.
+/// - `target = Name`: names the blocking type `Name` instead of adding
+///   `Blocking` to the name of the struct.
+/// - `prefix = "Sync"`, `suffix = "Blocking"`: names the blocking type by adding
+///   a prefix and/or a suffix to the name of the struct.
+///
+/// # Errors
+///
+/// Methods taking self by value can not be delegated to through an `Arc`:
+///
+/// ```compile_fail
+/// #[ut::newtype(arc)]
+/// pub struct Client {
+///   url: String,
+/// }
+///
+/// #[ut::clone_impl(delegate = inner)]
+/// impl Client {
+///   pub async fn close(self) -> String {
+///     self.url
+///   }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn newtype(meta: TokenStream, input: TokenStream) -> TokenStream {
+  // parse the arguments passed to our macro
+  let meta = syn::parse_macro_input!(meta as args::NewtypeArgs);
+  // parse the input stream into our async struct
+  let item = syn::parse_macro_input!(input as syn::ItemStruct);
+  // get visibility of struct
+  let vis = &item.vis;
+  // get the name of our struct
+  let name = &item.ident;
+  // get the name of our blocking type
+  let sync_name = match meta.name.ident(name) {
+    Ok(sync_name) => sync_name,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  // get information on the generics to pass
+  let generics = &item.generics;
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  // get the async type and the type holding it
+  let async_ty = quote!(#name #ty_generics);
+  let inner_ty = match &meta.arc {
+    Some(_) => quote!(::std::sync::Arc<#async_ty>),
+    None => async_ty.clone(),
+  };
+  // shared values can also be wrapped as is and cloned cheaply
+  let arc_impls = match &meta.arc {
+    Some(_) => quote!{
+      impl #impl_generics ::std::convert::From<#inner_ty> for #sync_name #ty_generics #where_clause {
+        fn from(inner: #inner_ty) -> Self {
+          #sync_name { inner }
+        }
+      }
+
+      impl #impl_generics ::std::clone::Clone for #sync_name #ty_generics #where_clause {
+        fn clone(&self) -> Self {
+          #sync_name { inner: ::std::sync::Arc::clone(&self.inner) }
+        }
+      }
+    },
+    None => quote!(),
+  };
+  let wrap = match &meta.arc {
+    Some(_) => quote!(::std::sync::Arc::new(inner)),
+    None => quote!(inner),
+  };
+  // get the derives that still hold for the blocking type
+  let derives: &[&str] = match &meta.arc {
+    Some(_) => &["Debug"],
+    None => &["Debug", "Clone"],
+  };
+  let attrs = fields::attrs(&item.attrs, derives);
+  let doc = format!("A blocking version of [`{}`]", name);
+  // cast back to a token stream
+  let output = quote!{
+    // add the original struct
+    #item
+
+    // add the blocking type holding the async value
+    #[doc = #doc]
+    #(#attrs)*
+    #vis struct #sync_name #generics #where_clause {
+      inner: #inner_ty,
+    }
+
+    #[allow(dead_code)]
+    impl #impl_generics #sync_name #ty_generics #where_clause {
+      /// Turns this back into the async value
+      #vis fn into_async(self) -> #inner_ty {
+        self.inner
+      }
+
+      /// Borrows the async value
+      #vis fn as_async(&self) -> &#async_ty {
+        &self.inner
+      }
+    }
+
+    impl #impl_generics ::std::convert::From<#async_ty> for #sync_name #ty_generics #where_clause {
+      fn from(inner: #async_ty) -> Self {
+        #sync_name { inner: #wrap }
+      }
+    }
+
+    impl #impl_generics ::std::convert::From<#sync_name #ty_generics> for #inner_ty #where_clause {
+      fn from(blocking: #sync_name #ty_generics) -> Self {
+        blocking.inner
+      }
+    }
+
+    impl #impl_generics ::ut::Blocking for #async_ty #where_clause {
+      type Blocking = #sync_name #ty_generics;
+    }
+
+    #arc_impls
+  };
+  output.into()
+}
+
+/// Creates the blocking version of a struct holding async types
+///
+/// The blocking struct is named like the struct with Blocking added and every
+/// field holds the blocking version of its type as set through `ut::Blocking`,
+/// which [`macro@newtype`] and this macro implement for the types they are used on.
+/// Fields can be kept as is with `#[ut(keep)]` or left out with `#[ut(skip)]`.
+///
+/// Only the standard derives like `Debug`, `Clone` and `Default` are copied
+/// onto the blocking struct so the blocking types of its fields need them too.
+/// [`macro@newtype`] derives `Debug` and `Clone` where the struct does while the
+/// others have to be implemented by hand.
+///
+/// # Examples
+///
+/// ```
+/// #[ut::newtype]
+/// #[derive(Debug, Clone, Default)]
+/// pub struct Fooers;
+///
+/// #[ut::clone_impl(delegate = inner)]
+/// impl Fooers {
+///   pub async fn foo(&self, input: &str) -> String {
+///     format!("I am {} now", input)
+///   }
+/// }
+///
+/// impl Default for FooersBlocking {
+///   fn default() -> Self {
+///     Fooers.into()
+///   }
+/// }
+///
+/// #[ut::blocking_struct]
+/// #[derive(Debug, Clone, Default)]
+/// pub struct Example {
+///   pub fooers: Fooers,
+///   #[ut(keep)]
+///   pub name: String,
+///   #[ut(skip)]
+///   pub cache: Vec<String>,
+/// }
+///
+/// let example = ExampleBlocking::default().clone();
+/// assert_eq!(example.fooers.foo("sync"), "I am sync now".to_owned());
+/// assert_eq!(example.name, String::new());
+/// assert_eq!(format!("{:?}", example), "ExampleBlocking { fooers: FooersBlocking { inner: Fooers }, name: \"\" }")
+/// ```
+///
+/// # Arguments
+///
+/// - `target = Name`: names the blocking struct `Name` instead of adding
+///   `Blocking` to the name of the struct.
+/// - `prefix = "Sync"`, `suffix = "Blocking"`: names the blocking struct by
+///   adding a prefix and/or a suffix to the name of the struct.
+///
+/// # Errors
+///
+/// Fields whose type has no blocking version have to be kept or skipped:
+///
+/// ```compile_fail
+/// #[ut::blocking_struct]
+/// pub struct Example {
+///   pub name: String,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn blocking_struct(meta: TokenStream, input: TokenStream) -> TokenStream {
+  // parse the arguments passed to our macro
+  let meta = syn::parse_macro_input!(meta as args::TypeName);
+  // parse the input stream into our async struct
+  let mut item = syn::parse_macro_input!(input as syn::ItemStruct);
+  // take our options off of the fields
+  let modes = match fields::modes(&mut item.fields) {
+    Ok(modes) => modes,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  // get the name of our struct
+  let name = &item.ident;
+  // build the blocking struct from a copy of the async one
+  let mut sync_item = item.clone();
+  sync_item.ident = match meta.ident(name) {
+    Ok(sync_name) => sync_name,
+    Err(err) => return err.to_compile_error().into(),
+  };
+  let doc = format!("A blocking version of [`{}`]", name);
+  sync_item.attrs = fields::attrs(&item.attrs, fields::DERIVES);
+  sync_item.attrs.insert(0, syn::parse_quote!(#[doc = #doc]));
+  fields::map(&mut sync_item.fields, &modes);
+  // get the name of our blocking struct
+  let sync_name = &sync_item.ident;
+  // get information on the generics to pass
+  let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+  // cast back to a token stream
+  let output = quote!{
+    // add the original struct
+    #item
+
+    // add the blocking struct
+    #sync_item
+
+    impl #impl_generics ::ut::Blocking for #name #ty_generics #where_clause {
+      type Blocking = #sync_name #ty_generics;
+    }
+  };
+  output.into()
+}
@@ -0,0 +1,241 @@
+use proc_macro2::Span;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::args::{Cfg, Timeout};
+
+/// The defaults for the ut macros set in the Cargo.toml of the calling crate
+///
+/// These are read from a table like this one and are only used for the
+/// arguments that were not passed to a macro:
+///
+/// ```toml
+/// [package.metadata.ut]
+/// feature = "blocking"
+/// suffix = "_sync"
+/// executor = "crate::rt::block_on"
+/// timeout = "30s"
+/// ```
+#[derive(Default)]
+pub(crate) struct Defaults {
+  /// The function to block on futures with instead of the shared runtime
+  pub executor: Option<syn::Path>,
+  /// How long blocking functions wait before giving up
+  pub timeout: Option<Timeout>,
+  /// The cfg predicate that enables blocking functions
+  pub cfg: Option<Cfg>,
+  /// The prefix added to the name of cloned functions
+  pub prefix: Option<syn::LitStr>,
+  /// The suffix added to the name of cloned functions
+  pub suffix: Option<syn::LitStr>,
+  /// The manifest these defaults were read from
+  pub manifest: Option<syn::LitStr>,
+}
+
+impl Defaults {
+  /// Loads the defaults of the crate being compiled
+  ///
+  /// Crates without a manifest or without ut metadata have no defaults.
+  pub fn load() -> syn::Result<Self> {
+    // find the manifest of the crate using our macros
+    let dir = match std::env::var_os("CARGO_MANIFEST_DIR") {
+      Some(dir) => PathBuf::from(dir),
+      None => return Ok(Defaults::default()),
+    };
+    let path = dir.join("Cargo.toml");
+    let manifest = match std::fs::read_to_string(&path) {
+      Ok(manifest) => manifest,
+      Err(_) => return Ok(Defaults::default()),
+    };
+    Defaults::parse(&manifest, &path)
+  }
+
+  /// Parses the defaults out of the contents of the manifest at `path`
+  fn parse(manifest: &str, path: &Path) -> syn::Result<Self> {
+    let manifest: toml::Value = manifest.parse().map_err(|err| error(path, err))?;
+    let mut defaults = Defaults::default();
+    // remember where our defaults came from so changes to them can be tracked
+    if let Some(path) = path.to_str() {
+      defaults.manifest = Some(syn::LitStr::new(path, Span::call_site()));
+    }
+    // get our table out of the package metadata
+    let table = manifest
+      .get("package")
+      .and_then(|package| package.get("metadata"))
+      .and_then(|metadata| metadata.get("ut"));
+    let table = match table {
+      Some(table) => table.as_table().ok_or_else(|| error(path, "expected a table"))?,
+      None => return Ok(defaults),
+    };
+    for (key, value) in table {
+      // every default is written as a string
+      let value = match value.as_str() {
+        Some(value) => value,
+        None => return Err(error(path, format!("`{}` must be a string", key))),
+      };
+      let lit = syn::LitStr::new(value, Span::call_site());
+      match key.as_str() {
+        "executor" => match lit.parse() {
+          Ok(executor) => defaults.executor = Some(executor),
+          Err(_) => {
+            let msg = format!("invalid executor `{}`, expected a path like \"crate::rt::block_on\"", value);
+            return Err(error(path, msg));
+          },
+        },
+        "timeout" => defaults.timeout = Some(Timeout::from_lit(lit).map_err(|err| error(path, err))?),
+        "feature" | "cfg" => {
+          if defaults.cfg.is_some() {
+            return Err(error(path, "only one of `feature` and `cfg` can be set"));
+          }
+          // a feature is a shorthand for its cfg predicate
+          let lit = match key.as_str() {
+            "feature" => syn::LitStr::new(&format!("feature = {:?}", value), Span::call_site()),
+            _ => lit,
+          };
+          defaults.cfg = Some(Cfg::from_lit(lit).map_err(|err| error(path, err))?);
+        },
+        "prefix" => defaults.prefix = Some(lit),
+        "suffix" => defaults.suffix = Some(lit),
+        _ => {
+          let msg = format!(
+            "unknown key `{}`, expected `executor`, `timeout`, `feature`, `cfg`, `prefix` or `suffix`",
+            key,
+          );
+          return Err(error(path, msg));
+        },
+      }
+    }
+    Ok(defaults)
+  }
+}
+
+/// Builds an error about the ut metadata in the manifest at `path`
+fn error(path: &Path, msg: impl Display) -> syn::Error {
+  let msg = format!("invalid [package.metadata.ut] in {}: {}", path.display(), msg);
+  syn::Error::new(Span::call_site(), msg)
+}
+
+#[cfg(test)]
+mod tests {
+  use quote::quote;
+
+  use super::*;
+  use crate::args::{Args, CloneArgs, ImplArgs, TraitArgs};
+
+  /// Parses the defaults out of a manifest with `table` as its ut metadata
+  fn parse(table: &str) -> syn::Result<Defaults> {
+    let manifest = format!("[package]\nname = \"app\"\n\n[package.metadata.ut]\n{}", table);
+    Defaults::parse(&manifest, Path::new("app/Cargo.toml"))
+  }
+
+  /// Gets the message of the error parsing `table` fails with
+  fn error(table: &str) -> String {
+    match parse(table) {
+      Ok(_) => panic!("expected `{}` to be rejected", table),
+      Err(err) => err.to_string(),
+    }
+  }
+
+  #[test]
+  fn no_metadata() {
+    let defaults = Defaults::parse("[package]\nname = \"app\"\n", Path::new("app/Cargo.toml")).unwrap();
+    assert!(defaults.executor.is_none() && defaults.timeout.is_none() && defaults.cfg.is_none());
+    assert_eq!(defaults.manifest.unwrap().value(), "app/Cargo.toml");
+  }
+
+  #[test]
+  fn every_key() {
+    let table = "executor = \"crate::rt::block_on\"\ntimeout = \"2m\"\nfeature = \"blocking\"\n\
+                 prefix = \"sync_\"\nsuffix = \"_now\"";
+    let mut defaults = parse(table).unwrap();
+    let mut args = Args::default();
+    args.fill(&mut defaults);
+    let (executor, timeout) = (args.executor.as_ref().unwrap(), args.timeout.as_ref().unwrap());
+    assert_eq!(quote!(#executor).to_string(), quote!(crate::rt::block_on).to_string());
+    let millis = quote!(::std::time::Duration::from_millis(120000u64));
+    assert_eq!(quote!(#timeout).to_string(), millis.to_string());
+    assert_eq!(args.cfg().to_string(), quote!(feature = "blocking").to_string());
+    assert_eq!(defaults.prefix.unwrap().value(), "sync_");
+    assert_eq!(defaults.suffix.unwrap().value(), "_now");
+  }
+
+  #[test]
+  fn cfg() {
+    let mut args = Args::default();
+    args.fill(&mut parse("cfg = \"all(unix, feature = \\\"blocking\\\")\"").unwrap());
+    assert_eq!(args.cfg().to_string(), quote!(all(unix, feature = "blocking")).to_string());
+  }
+
+  #[test]
+  fn feature_and_cfg() {
+    let msg = error("feature = \"blocking\"\ncfg = \"unix\"");
+    assert!(msg.contains("only one of `feature` and `cfg` can be set"), "{}", msg);
+  }
+
+  #[test]
+  fn unknown_key() {
+    let msg = error("name = \"get\"");
+    assert!(msg.starts_with("invalid [package.metadata.ut] in app/Cargo.toml"), "{}", msg);
+    assert!(msg.contains("unknown key `name`"), "{}", msg);
+  }
+
+  #[test]
+  fn not_a_string() {
+    assert!(error("timeout = 30").contains("`timeout` must be a string"));
+    assert!(error("feature = [\"blocking\"]").contains("`feature` must be a string"));
+  }
+
+  #[test]
+  fn not_a_table() {
+    let manifest = "[package]\nname = \"app\"\nmetadata = { ut = \"blocking\" }\n";
+    let msg = match Defaults::parse(manifest, Path::new("app/Cargo.toml")) {
+      Ok(_) => panic!("expected a table"),
+      Err(err) => err.to_string(),
+    };
+    assert!(msg.contains("expected a table"), "{}", msg);
+  }
+
+  #[test]
+  fn bad_executor() {
+    let msg = error("executor = \"crate::rt::block_on()\"");
+    assert!(msg.contains("invalid executor `crate::rt::block_on()`"), "{}", msg);
+  }
+
+  #[test]
+  fn bad_timeout() {
+    assert!(error("timeout = \"30\"").contains("invalid timeout `30`"));
+    assert!(error("timeout = \"1.5s\"").contains("invalid timeout `1.5s`"));
+    assert!(error("timeout = \"99999999999999999999h\"").contains("invalid timeout"));
+  }
+
+  #[test]
+  fn bad_cfg() {
+    assert!(error("cfg = \"feature =\"").contains("invalid cfg predicate `feature =`"));
+  }
+
+  #[test]
+  fn affixes_only_name_clones() {
+    let defaults = || parse("prefix = \"sync_\"\nsuffix = \"_now\"").unwrap();
+    let get: syn::Ident = syn::parse_quote!(get);
+    // clones are named with the defaults unless they were named
+    let mut clone = CloneArgs::default();
+    clone.fill(defaults());
+    assert_eq!(clone.sync_ident(&get).unwrap(), "sync_get_now");
+    assert_eq!(clone.try_ident(&get).unwrap(), "try_sync_get_now");
+    let mut clone: CloneArgs = syn::parse_quote!(suffix = "_sync");
+    clone.fill(defaults());
+    assert_eq!(clone.sync_ident(&get).unwrap(), "get_sync");
+    let mut clone: CloneArgs = syn::parse_quote!(name = "fetch");
+    clone.fill(defaults());
+    assert_eq!(clone.sync_ident(&get).unwrap(), "fetch");
+    // blocking types, traits and methods keep their names
+    let mut imp = ImplArgs::default();
+    imp.args.fill(&mut defaults());
+    assert_eq!(imp.sync_ident(&syn::parse_quote!(Http)).unwrap(), "HttpBlocking");
+    assert_eq!(imp.method_ident(&get).unwrap(), "get");
+    let mut tr = TraitArgs::default();
+    tr.args.fill(&mut defaults());
+    assert_eq!(tr.name.ident(&syn::parse_quote!(Api)).unwrap(), "ApiBlocking");
+    assert_eq!(tr.method_ident(&get).unwrap(), "get");
+  }
+}
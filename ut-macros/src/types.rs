@@ -0,0 +1,97 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+/// The async types that blocking methods return the blocking versions of
+///
+/// Types are given like `map(Users, Orders = OrderList)`, where types without
+/// a blocking type of their own are mapped through `ut::Blocking`.
+#[derive(Default)]
+pub(crate) struct TypeMap {
+  /// Every async type and the blocking type it is mapped to
+  types: Vec<(syn::Type, syn::Type)>,
+}
+
+impl Parse for TypeMap {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let entries = Punctuated::<(syn::Type, Option<syn::Type>), Token![,]>::parse_terminated_with(input, |input| {
+      let async_ty: syn::Type = input.parse()?;
+      let sync_ty = match input.parse::<Option<Token![=]>>()? {
+        Some(_) => Some(input.parse()?),
+        None => None,
+      };
+      Ok((async_ty, sync_ty))
+    })?;
+    let types = entries.into_iter().map(|(async_ty, sync_ty)| {
+      let sync_ty = sync_ty.unwrap_or_else(|| syn::parse_quote!(<#async_ty as ::ut::Blocking>::Blocking));
+      (async_ty, sync_ty)
+    }).collect();
+    Ok(TypeMap { types })
+  }
+}
+
+impl TypeMap {
+  /// Points a return type at the blocking versions of the async types it names
+  ///
+  /// Types are mapped on their own or inside of a `Result` or an `Option`.
+  /// `Self` is also converted when it is the blocking type holding the async
+  /// value. Returns how to convert the value the async method returned.
+  pub fn output(&self, output: &mut syn::ReturnType, map_self: bool) -> Option<Convert> {
+    match output {
+      syn::ReturnType::Type(_, ty) => self.convert(ty, map_self),
+      syn::ReturnType::Default => None,
+    }
+  }
+
+  /// Maps a type, returning how to convert its values if it changed
+  fn convert(&self, ty: &mut syn::Type, map_self: bool) -> Option<Convert> {
+    // types are compared as they were written
+    let written = quote!(#ty).to_string();
+    if map_self && written == "Self" {
+      return Some(Convert::Into);
+    }
+    if let Some((_, sync_ty)) = self.types.iter().find(|(async_ty, _)| quote!(#async_ty).to_string() == written) {
+      *ty = sync_ty.clone();
+      return Some(Convert::Into);
+    }
+    // look inside of results and options for the value they hold
+    let segment = match ty {
+      syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last_mut()?,
+      _ => return None,
+    };
+    if segment.ident != "Result" && segment.ident != "Option" {
+      return None;
+    }
+    let inner = match &mut segment.arguments {
+      syn::PathArguments::AngleBracketed(args) => match args.args.first_mut()? {
+        syn::GenericArgument::Type(inner) => inner,
+        _ => return None,
+      },
+      _ => return None,
+    };
+    self.convert(inner, map_self).map(|inner| Convert::Map(Box::new(inner)))
+  }
+}
+
+/// How to turn the value of an async type into the value of its blocking type
+pub(crate) enum Convert {
+  /// Convert the value itself with `Into`
+  Into,
+  /// Convert the value held by a `Result` or an `Option`
+  Map(Box<Convert>),
+}
+
+impl Convert {
+  /// Gets an expression converting `value`
+  pub fn apply(&self, value: TokenStream) -> TokenStream {
+    match self {
+      Convert::Into => quote!(::std::convert::Into::into(#value)),
+      Convert::Map(inner) => {
+        let inner = inner.apply(quote!(__ut_value));
+        quote!(#value.map(|__ut_value| #inner))
+      },
+    }
+  }
+}
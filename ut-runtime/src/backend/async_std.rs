@@ -0,0 +1,16 @@
+use std::future::Future;
+use crate::background::Job;
+use crate::BlockingError;
+
+/// Runs a future to completion on the async-std executor
+pub(crate) fn block_on<F: Future>(future: F) -> Result<F::Output, BlockingError> {
+  Ok(::async_std::task::block_on(future))
+}
+
+/// Spawns a job onto the async-std executor
+///
+/// async-std already runs its executor on long lived background threads.
+pub(crate) fn spawn(job: Job) -> Result<(), BlockingError> {
+  ::async_std::task::spawn(job);
+  Ok(())
+}
@@ -0,0 +1,33 @@
+use std::future::Future;
+use once_cell::sync::OnceCell;
+use ::futures_executor::ThreadPool;
+use crate::background::Job;
+use crate::BlockingError;
+
+/// The thread pool background jobs are run on
+static POOL: OnceCell<ThreadPool> = OnceCell::new();
+
+/// Runs a future to completion with the futures executor
+///
+/// The futures executor panics when it is started from inside of itself so we
+/// check for that first.
+pub(crate) fn block_on<F: Future>(future: F) -> Result<F::Output, BlockingError> {
+  match ::futures_executor::enter() {
+    Ok(enter) => drop(enter),
+    Err(_) => return Err(BlockingError::NestedRuntime),
+  }
+  Ok(::futures_executor::block_on(future))
+}
+
+/// Spawns a job onto the background thread pool
+pub(crate) fn spawn(job: Job) -> Result<(), BlockingError> {
+  let pool = POOL.get_or_try_init(|| {
+    ThreadPool::builder()
+      .pool_size(1)
+      .name_prefix("ut-background-")
+      .create()
+      .map_err(BlockingError::Runtime)
+  })?;
+  pool.spawn_ok(job);
+  Ok(())
+}
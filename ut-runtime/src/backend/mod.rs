@@ -0,0 +1,48 @@
+//! The executors that blocking calls can be run on
+//!
+//! Each backend provides a `block_on` that runs a future to completion on the
+//! current thread and a `spawn` that runs a job on a background thread. Only
+//! one backend is used even if several are enabled so that the compile error
+//! in the crate root is the only error reported. For the same reason a stub
+//! backend is used when none of them are enabled.
+
+#[cfg(feature = "runtime-tokio")]
+mod tokio;
+#[cfg(feature = "runtime-async-std")]
+mod async_std;
+#[cfg(feature = "runtime-smol")]
+mod smol;
+#[cfg(feature = "runtime-futures-executor")]
+mod futures_executor;
+#[cfg(not(any(
+  feature = "runtime-tokio",
+  feature = "runtime-async-std",
+  feature = "runtime-smol",
+  feature = "runtime-futures-executor",
+)))]
+mod none;
+
+#[cfg(feature = "runtime-tokio")]
+pub(crate) use self::tokio::*;
+#[cfg(all(
+  feature = "runtime-async-std",
+  not(feature = "runtime-tokio"),
+))]
+pub(crate) use self::async_std::*;
+#[cfg(all(
+  feature = "runtime-smol",
+  not(any(feature = "runtime-tokio", feature = "runtime-async-std")),
+))]
+pub(crate) use self::smol::*;
+#[cfg(all(
+  feature = "runtime-futures-executor",
+  not(any(feature = "runtime-tokio", feature = "runtime-async-std", feature = "runtime-smol")),
+))]
+pub(crate) use self::futures_executor::*;
+#[cfg(not(any(
+  feature = "runtime-tokio",
+  feature = "runtime-async-std",
+  feature = "runtime-smol",
+  feature = "runtime-futures-executor",
+)))]
+pub(crate) use self::none::*;
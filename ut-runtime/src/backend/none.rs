@@ -0,0 +1,15 @@
+use std::future::Future;
+use crate::background::Job;
+use crate::BlockingError;
+
+/// Stands in for the `block_on` of a backend when no runtime is enabled
+///
+/// This is never run as the crate root fails to compile without a runtime.
+pub(crate) fn block_on<F: Future>(_future: F) -> Result<F::Output, BlockingError> {
+  unreachable!("ut was built without a runtime")
+}
+
+/// Stands in for the `spawn` of a backend when no runtime is enabled
+pub(crate) fn spawn(_job: Job) -> Result<(), BlockingError> {
+  unreachable!("ut was built without a runtime")
+}
@@ -0,0 +1,31 @@
+use std::future::{self, Future};
+use std::thread;
+use once_cell::sync::OnceCell;
+use ::smol::Executor;
+use crate::background::Job;
+use crate::BlockingError;
+
+/// The executor run by the background thread
+static EXECUTOR: Executor<'static> = Executor::new();
+
+/// Whether the background thread has been started
+static BACKGROUND: OnceCell<()> = OnceCell::new();
+
+/// Runs a future to completion with smol
+pub(crate) fn block_on<F: Future>(future: F) -> Result<F::Output, BlockingError> {
+  Ok(::smol::block_on(future))
+}
+
+/// Spawns a job onto the executor run by the background thread
+pub(crate) fn spawn(job: Job) -> Result<(), BlockingError> {
+  BACKGROUND.get_or_try_init(|| {
+    // run the executor forever so the jobs spawned onto it keep running
+    thread::Builder::new()
+      .name("ut-background".to_owned())
+      .spawn(|| ::smol::block_on(EXECUTOR.run(future::pending::<()>())))
+      .map(drop)
+      .map_err(BlockingError::Runtime)
+  })?;
+  EXECUTOR.spawn(job).detach();
+  Ok(())
+}
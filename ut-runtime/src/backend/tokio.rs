@@ -0,0 +1,89 @@
+use std::future::{self, Future};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use once_cell::sync::OnceCell;
+use ::tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+use ::tokio::task;
+use crate::background::Job;
+use crate::{BlockingError, Config};
+
+/// The runtime shared by all blocking calls in this process
+static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+
+/// A handle to the runtime owned by the background thread
+static BACKGROUND: OnceCell<Handle> = OnceCell::new();
+
+/// Gets the shared runtime building it if this is the first call
+///
+/// A runtime that failed to build is not cached so the next call tries again.
+fn runtime() -> Result<&'static Runtime, BlockingError> {
+  RUNTIME.get_or_try_init(|| Config::current().build_runtime().map_err(BlockingError::Runtime))
+}
+
+/// Runs a future to completion on the shared tokio runtime
+///
+/// If we are already inside of a multi threaded runtime then the future is run
+/// on that runtime instead after handing this worker's tasks off to the other
+/// workers. Threads of other runtimes poll the future themselves while the
+/// shared runtime drives its timers and IO, as tokio can not tell us if they
+/// are running async code or only hold a handle like `spawn_blocking` does.
+pub(crate) fn block_on<F: Future>(future: F) -> Result<F::Output, BlockingError> {
+  match Handle::try_current() {
+    Ok(handle) => match handle.runtime_flavor() {
+      RuntimeFlavor::MultiThread => Ok(task::block_in_place(|| handle.block_on(future))),
+      _ => poll_here(future),
+    },
+    Err(_) => Ok(runtime()?.block_on(future)),
+  }
+}
+
+/// Wakes a thread that is waiting on a future
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+  fn wake(self: Arc<Self>) {
+    self.0.unpark();
+  }
+}
+
+/// Polls a future on this thread until it finishes
+///
+/// The shared runtime is entered while polling so the timers, IO and tasks the
+/// future starts belong to it and are driven by its workers. Entering it like
+/// this works even when this thread is already running another runtime.
+fn poll_here<F: Future>(future: F) -> Result<F::Output, BlockingError> {
+  let _enter = runtime()?.enter();
+  let mut future = Box::pin(future);
+  let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+  let mut cx = Context::from_waker(&waker);
+  loop {
+    match future.as_mut().poll(&mut cx) {
+      Poll::Ready(output) => return Ok(output),
+      Poll::Pending => thread::park(),
+    }
+  }
+}
+
+/// Gets the background runtime starting its thread if this is the first call
+fn background() -> Result<&'static Handle, BlockingError> {
+  BACKGROUND.get_or_try_init(|| {
+    let runtime = Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .map_err(BlockingError::Runtime)?;
+    let handle = runtime.handle().clone();
+    // drive the runtime forever so the jobs spawned onto it keep running
+    thread::Builder::new()
+      .name("ut-background".to_owned())
+      .spawn(move || runtime.block_on(future::pending::<()>()))
+      .map_err(BlockingError::Runtime)?;
+    Ok(handle)
+  })
+}
+
+/// Spawns a job onto the runtime owned by the background thread
+pub(crate) fn spawn(job: Job) -> Result<(), BlockingError> {
+  background()?.spawn(job);
+  Ok(())
+}
@@ -0,0 +1,236 @@
+//! Runs blocking calls on a background thread that owns the runtime
+//!
+//! This works like `reqwest::blocking`. Each future is sent to a long lived
+//! background thread and the caller waits on a oneshot channel for its output.
+//! Nothing is run on the caller's thread so these calls can be made from inside
+//! of another runtime. The background runtime lives for the whole process so
+//! connection pools survive across calls.
+//!
+//! The futures have to be `Send` as they are run on another thread.
+//!
+//! The one place these calls can not be made from is a future that is itself
+//! running on the background runtime. Waiting there would stall the thread the
+//! inner future has to run on, so these calls give back
+//! [`BlockingError::NestedRuntime`] instead.
+//!
+//! # Examples
+//!
+//! ```
+//! async fn foo(input: &str) -> String {
+//!   format!("I am {} now", input)
+//! }
+//!
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() {
+//!   // this is what #[ut::clone(background)] generates for foo_blocking
+//!   let out = ut::background::block_on(foo("sync"));
+//!   assert_eq!(out, "I am sync now".to_owned());
+//! }
+//! ```
+//!
+//! Blocking again from inside of a background call gives an error:
+//!
+//! ```
+//! let err = ut::background::block_on(async {
+//!   ut::background::try_block_on(async { 1 }).unwrap_err()
+//! });
+//! assert!(matches!(err, ut::BlockingError::NestedRuntime));
+//! ```
+
+use std::future::Future;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{self, SyncSender};
+use std::task::{Context, Poll};
+use std::thread;
+use std::cell::Cell;
+use std::time::Duration;
+use crate::{backend, default_timeout, BlockingError};
+
+thread_local! {
+  /// Whether this thread is polling a future for the background runtime
+  static IN_BACKGROUND: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A future that can be sent to the background thread
+pub(crate) type Job = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// A job that still borrows from the caller
+type BorrowedJob<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Wraps a future to send its output back to the caller waiting on it
+///
+/// The future is always dropped before the sender so the caller can not stop
+/// waiting while the future is still alive.
+struct Task<'a, T> {
+  /// The future we are running or None if it is finished
+  future: Option<Pin<Box<dyn Future<Output = T> + Send + 'a>>>,
+  /// Sends the output of the future to the caller
+  sender: SyncSender<thread::Result<T>>,
+}
+
+impl<T> Future for Task<'_, T> {
+  type Output = ();
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+    let task = &mut *self;
+    let future = match &mut task.future {
+      Some(future) => future,
+      None => return Poll::Ready(()),
+    };
+    // poll the future catching any panics so they can be sent to the caller
+    let entered = IN_BACKGROUND.with(|flag| flag.replace(true));
+    let polled = panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx)));
+    IN_BACKGROUND.with(|flag| flag.set(entered));
+    let output = match polled {
+      Ok(Poll::Pending) => return Poll::Pending,
+      Ok(Poll::Ready(output)) => Ok(output),
+      Err(panic) => Err(panic),
+    };
+    // drop the future before we let the caller return
+    drop(task.future.take());
+    let _ = task.sender.send(output);
+    Poll::Ready(())
+  }
+}
+
+/// Runs a future to completion on the background runtime
+///
+/// Panics in the future are resumed on the calling thread. This gives up once
+/// the timeout set with [`set_default_timeout`](crate::set_default_timeout)
+/// passes.
+///
+/// # Panics
+///
+/// This panics with the error [`try_block_on`] would return if the future
+/// could not be run.
+///
+/// # Examples
+///
+/// The caller gets the payload the future panicked with:
+///
+/// ```
+/// use std::panic;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Lost(u32);
+///
+/// async fn run() -> u32 {
+///   panic::panic_any(Lost(3))
+/// }
+///
+/// let panic = panic::catch_unwind(|| ut::background::block_on(run())).unwrap_err();
+/// assert_eq!(panic.downcast_ref::<Lost>(), Some(&Lost(3)))
+/// ```
+pub fn block_on<F>(future: F) -> F::Output
+where
+  F: Future + Send,
+  F::Output: Send,
+{
+  resume(run(future, default_timeout()))
+}
+
+/// Runs a future to completion on the background runtime returning an error if
+/// it could not be run
+///
+/// Panics in the future are caught and returned as [`BlockingError::Panicked`].
+///
+/// # Examples
+///
+/// ```
+/// async fn run(jobs: u32) -> u32 {
+///   panic!("lost {} jobs", jobs)
+/// }
+///
+/// let err = ut::background::try_block_on(run(3)).unwrap_err();
+/// assert!(matches!(err, ut::BlockingError::Panicked(msg) if msg == "lost 3 jobs"));
+/// // the background thread keeps running other calls
+/// assert_eq!(ut::background::try_block_on(async { 3 }).unwrap(), 3)
+/// ```
+pub fn try_block_on<F>(future: F) -> Result<F::Output, BlockingError>
+where
+  F: Future + Send,
+  F::Output: Send,
+{
+  run(future, default_timeout())?.map_err(|panic| BlockingError::panicked(&*panic))
+}
+
+/// Runs a future to completion on the background runtime giving up once the
+/// timeout passes
+///
+/// Panics in the future are resumed on the calling thread.
+///
+/// # Panics
+///
+/// This panics with the error [`try_block_on_timeout`] would return if the
+/// future could not be run or timed out.
+pub fn block_on_timeout<F>(future: F, timeout: Duration) -> F::Output
+where
+  F: Future + Send,
+  F::Output: Send,
+{
+  resume(run(future, Some(timeout)))
+}
+
+/// Runs a future to completion on the background runtime returning
+/// [`BlockingError::Timeout`] once the timeout passes
+///
+/// Panics in the future are caught and returned as [`BlockingError::Panicked`].
+pub fn try_block_on_timeout<F>(future: F, timeout: Duration) -> Result<F::Output, BlockingError>
+where
+  F: Future + Send,
+  F::Output: Send,
+{
+  run(future, Some(timeout))?.map_err(|panic| BlockingError::panicked(&*panic))
+}
+
+/// Gets the output of a background call resuming its panic on this thread
+fn resume<T>(result: Result<thread::Result<T>, BlockingError>) -> T {
+  match result {
+    Ok(Ok(output)) => output,
+    Ok(Err(panic)) => panic::resume_unwind(panic),
+    Err(err) => panic!("{}", err),
+  }
+}
+
+/// Runs a future to completion on the background runtime with an optional
+/// timeout catching any panics
+fn run<F>(future: F, timeout: Option<Duration>) -> Result<thread::Result<F::Output>, BlockingError>
+where
+  F: Future + Send,
+  F::Output: Send,
+{
+  match timeout {
+    Some(limit) => match spawn(crate::timeout(limit, future))? {
+      Ok(Ok(output)) => Ok(Ok(output)),
+      Ok(Err(err)) => Err(err),
+      Err(panic) => Ok(Err(panic)),
+    },
+    None => spawn(future),
+  }
+}
+
+/// Spawns a future onto the background runtime and waits for its output
+///
+/// Gives back [`BlockingError::NestedRuntime`] when called from a future that
+/// is running on the background runtime as waiting there would never finish.
+fn spawn<F>(future: F) -> Result<thread::Result<F::Output>, BlockingError>
+where
+  F: Future + Send,
+  F::Output: Send,
+{
+  if IN_BACKGROUND.with(Cell::get) {
+    return Err(BlockingError::NestedRuntime);
+  }
+  let (sender, receiver) = mpsc::sync_channel(1);
+  let task = Task { future: Some(Box::pin(future)), sender };
+  let job: BorrowedJob<'_> = Box::pin(task);
+  // SAFETY: the job may borrow from the caller so it can not outlive this call.
+  // We wait below until the sender is used or dropped and the task only does
+  // either after the future has been dropped. This holds even if the job is
+  // never polled as the task drops its future before its sender.
+  let job = unsafe { mem::transmute::<BorrowedJob<'_>, Job>(job) };
+  backend::spawn(job)?;
+  receiver.recv().map_err(|_| BlockingError::BackgroundStopped)
+}
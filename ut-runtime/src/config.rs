@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use once_cell::sync::OnceCell;
+
+/// The config installed for this process
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// The default timeout for blocking calls in nanoseconds
+///
+/// This is `u64::MAX` when blocking calls have no default timeout.
+static DEFAULT_TIMEOUT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Configures the runtime shared by all blocking calls
+///
+/// The config must be installed before the first blocking call as that is when
+/// the shared runtime is built. Blocking calls made without installing a config
+/// use [`Config::default`]. The runtime settings are only used by the tokio
+/// backend as the other backends do not build a runtime of their own.
+///
+/// # Examples
+///
+/// ```
+/// ut::Config::new()
+///   .worker_threads(2)
+///   .thread_name("api-client")
+///   .install()
+///   .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+  /// The number of worker threads to run
+  worker_threads: Option<usize>,
+  /// The name to give the worker threads
+  thread_name: Option<String>,
+}
+
+impl Config {
+  /// Creates a config with the default runtime settings
+  pub fn new() -> Self {
+    Config::default()
+  }
+
+  /// Sets the number of worker threads the shared runtime uses
+  ///
+  /// This defaults to the number of cores on the system.
+  pub fn worker_threads(mut self, worker_threads: usize) -> Self {
+    self.worker_threads = Some(worker_threads);
+    self
+  }
+
+  /// Sets the name of the worker threads the shared runtime spawns
+  pub fn thread_name(mut self, thread_name: impl Into<String>) -> Self {
+    self.thread_name = Some(thread_name.into());
+    self
+  }
+
+  /// Installs this config for the whole process
+  ///
+  /// This gives the config back if a config was already installed or if a
+  /// blocking call already built the shared runtime.
+  pub fn install(self) -> Result<(), Config> {
+    CONFIG.set(self)
+  }
+
+  /// Gets the installed config falling back to the default one
+  #[cfg_attr(not(feature = "runtime-tokio"), allow(dead_code))]
+  pub(crate) fn current() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+  }
+
+  /// Builds a tokio runtime from this config
+  #[cfg(feature = "runtime-tokio")]
+  pub(crate) fn build_runtime(&self) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(worker_threads) = self.worker_threads {
+      builder.worker_threads(worker_threads);
+    }
+    if let Some(thread_name) = &self.thread_name {
+      builder.thread_name(thread_name);
+    }
+    builder.build()
+  }
+}
+
+/// Sets the timeout used by blocking calls that were not given one
+///
+/// This can be changed at any time and applies to every blocking call that
+/// starts afterwards. Blocking calls that time out panic unless they are
+/// fallible in which case they return [`BlockingError::Timeout`]. Passing `None`
+/// removes the default timeout which is how ut starts out.
+///
+/// [`BlockingError::Timeout`]: crate::BlockingError::Timeout
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// ut::set_default_timeout(Some(Duration::from_millis(10)));
+/// let err = ut::try_block_on(std::future::pending::<()>()).unwrap_err();
+/// assert!(matches!(err, ut::BlockingError::Timeout(_)));
+/// ```
+pub fn set_default_timeout(timeout: Option<Duration>) {
+  // clamp timeouts that do not fit so they can not be confused with None
+  let nanos = match timeout {
+    Some(timeout) => timeout.as_nanos().min(u128::from(u64::MAX - 1)) as u64,
+    None => u64::MAX,
+  };
+  DEFAULT_TIMEOUT.store(nanos, Ordering::Relaxed);
+}
+
+/// Gets the timeout used by blocking calls that were not given one
+pub fn default_timeout() -> Option<Duration> {
+  match DEFAULT_TIMEOUT.load(Ordering::Relaxed) {
+    u64::MAX => None,
+    nanos => Some(Duration::from_nanos(nanos)),
+  }
+}
@@ -0,0 +1,65 @@
+use std::any::Any;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// An error that stopped a blocking call from running its future
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BlockingError {
+  /// The shared runtime could not be built
+  Runtime(io::Error),
+  /// The blocking call was made from inside of a runtime that can not block
+  ///
+  /// This happens when blocking inside of the futures executor as blocking
+  /// there would stall every other task on that runtime. Background calls made from a future that is running
+  /// on the background runtime give this back for the same reason.
+  NestedRuntime,
+  /// The background runtime stopped before the blocking call finished
+  BackgroundStopped,
+  /// The future did not finish before the timeout passed
+  Timeout(Duration),
+  /// The future panicked while running on the background runtime
+  ///
+  /// This contains the panic message if it was a string.
+  Panicked(String),
+}
+
+impl BlockingError {
+  /// Builds an error from the payload of a panic
+  pub(crate) fn panicked(panic: &(dyn Any + Send)) -> Self {
+    let msg = match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+      (Some(msg), _) => (*msg).to_owned(),
+      (None, Some(msg)) => msg.clone(),
+      (None, None) => "Box<dyn Any>".to_owned(),
+    };
+    BlockingError::Panicked(msg)
+  }
+}
+
+impl fmt::Display for BlockingError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BlockingError::Runtime(err) => write!(f, "failed to build the ut runtime: {}", err),
+      BlockingError::NestedRuntime => {
+        write!(f, "can not block from inside of a runtime that does not support blocking")
+      },
+      BlockingError::BackgroundStopped => {
+        write!(f, "the ut background runtime stopped before the blocking call finished")
+      },
+      BlockingError::Timeout(timeout) => {
+        write!(f, "the blocking call timed out after {:?}", timeout)
+      },
+      BlockingError::Panicked(msg) => write!(f, "the blocking call panicked: {}", msg),
+    }
+  }
+}
+
+impl std::error::Error for BlockingError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      BlockingError::Runtime(err) => Some(err),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,99 @@
+use std::future::Future;
+use std::time::Duration;
+use crate::{backend, default_timeout, BlockingError};
+
+/// Runs a future to completion on the executor picked by the runtime features
+///
+/// This is what the functions generated by the ut macros block with. It gives up
+/// once the timeout set with [`set_default_timeout`](crate::set_default_timeout)
+/// passes.
+///
+/// # Panics
+///
+/// This panics with the error [`try_block_on`] would return if the future
+/// could not be run.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+  expect(try_block_on(future))
+}
+
+/// Runs a future to completion returning an error if it could not be run
+///
+/// Blocking from inside of a multi threaded tokio runtime runs the future on
+/// that runtime without stalling its other tasks. A current thread tokio runtime
+/// is stalled until the future finishes, so blocking there should be done from
+/// `spawn_blocking`. Executors that can not be blocked at all such as the
+/// futures executor give back [`BlockingError::NestedRuntime`].
+///
+/// # Examples
+///
+/// ```
+/// #[tokio::main(flavor = "multi_thread")]
+/// async fn main() {
+///   assert_eq!(ut::try_block_on(async { 1 }).unwrap(), 1);
+/// }
+/// ```
+///
+/// ```
+/// use std::time::Duration;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() {
+///   let out = tokio::task::spawn_blocking(|| {
+///     ut::try_block_on(async {
+///       tokio::time::sleep(Duration::from_millis(1)).await;
+///       1
+///     })
+///   });
+///   assert_eq!(out.await.unwrap().unwrap(), 1);
+///   assert_eq!(ut::try_block_on(async { 2 }).unwrap(), 2);
+/// }
+/// ```
+pub fn try_block_on<F: Future>(future: F) -> Result<F::Output, BlockingError> {
+  run(future, default_timeout())
+}
+
+/// Runs a future to completion giving up once the timeout passes
+///
+/// # Panics
+///
+/// This panics with the error [`try_block_on_timeout`] would return if the
+/// future could not be run or timed out.
+pub fn block_on_timeout<F: Future>(future: F, timeout: Duration) -> F::Output {
+  expect(try_block_on_timeout(future, timeout))
+}
+
+/// Runs a future to completion returning [`BlockingError::Timeout`] once the
+/// timeout passes
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let forever = std::future::pending::<()>();
+/// let err = ut::try_block_on_timeout(forever, Duration::from_millis(10)).unwrap_err();
+/// assert!(matches!(err, ut::BlockingError::Timeout(_)));
+/// ```
+pub fn try_block_on_timeout<F: Future>(
+  future: F,
+  timeout: Duration,
+) -> Result<F::Output, BlockingError> {
+  run(future, Some(timeout))
+}
+
+/// Gets the output of a blocking call panicking if it failed
+#[doc(hidden)]
+pub fn expect<T>(result: Result<T, BlockingError>) -> T {
+  match result {
+    Ok(output) => output,
+    Err(err) => panic!("{}", err),
+  }
+}
+
+/// Runs a future to completion with an optional timeout
+fn run<F: Future>(future: F, timeout: Option<Duration>) -> Result<F::Output, BlockingError> {
+  match timeout {
+    Some(limit) => backend::block_on(crate::timeout(limit, future))?,
+    None => backend::block_on(future),
+  }
+}
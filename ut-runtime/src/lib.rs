@@ -0,0 +1,41 @@
+//! Runtime support for [ut](https://docs.rs/ut)
+//!
+//! This crate contains the executor, config and error types that the code
+//! generated by the ut macros relies on. It should be used through the ut
+//! crate which re-exports everything needed.
+
+#[cfg(any(
+  all(feature = "runtime-tokio", feature = "runtime-async-std"),
+  all(feature = "runtime-tokio", feature = "runtime-smol"),
+  all(feature = "runtime-tokio", feature = "runtime-futures-executor"),
+  all(feature = "runtime-async-std", feature = "runtime-smol"),
+  all(feature = "runtime-async-std", feature = "runtime-futures-executor"),
+  all(feature = "runtime-smol", feature = "runtime-futures-executor"),
+))]
+compile_error!(
+  "only one of the runtime-tokio, runtime-async-std, runtime-smol and \
+   runtime-futures-executor features of ut can be enabled at once"
+);
+
+#[cfg(not(any(
+  feature = "runtime-tokio",
+  feature = "runtime-async-std",
+  feature = "runtime-smol",
+  feature = "runtime-futures-executor",
+)))]
+compile_error!(
+  "ut needs one of the runtime-tokio, runtime-async-std, runtime-smol or \
+   runtime-futures-executor features to be enabled"
+);
+
+pub mod background;
+mod backend;
+mod config;
+mod error;
+mod executor;
+mod timeout;
+
+pub use config::{default_timeout, set_default_timeout, Config};
+pub use error::BlockingError;
+pub use executor::{block_on, block_on_timeout, expect, try_block_on, try_block_on_timeout};
+pub use timeout::{timeout, timeout_default, Timeout};
@@ -0,0 +1,61 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use futures_timer::Delay;
+use crate::{default_timeout, BlockingError};
+
+/// Gives up on a future once a timeout has passed
+///
+/// This uses its own timer so it works on any executor.
+#[derive(Debug)]
+pub struct Timeout<F> {
+  /// The future we are waiting on
+  future: Pin<Box<F>>,
+  /// Fires once the timeout has passed along with how long it is, or None if
+  /// we never give up
+  limit: Option<(Delay, Duration)>,
+}
+
+impl<F: Future> Future for Timeout<F> {
+  type Output = Result<F::Output, BlockingError>;
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+      return Poll::Ready(Ok(output));
+    }
+    match &mut self.limit {
+      Some((delay, timeout)) => match Pin::new(delay).poll(cx) {
+        Poll::Ready(()) => Poll::Ready(Err(BlockingError::Timeout(*timeout))),
+        Poll::Pending => Poll::Pending,
+      },
+      None => Poll::Pending,
+    }
+  }
+}
+
+/// Runs a future until it finishes or the timeout passes
+pub fn timeout<F: Future>(timeout: Duration, future: F) -> Timeout<F> {
+  Timeout { future: Box::pin(future), limit: Some((Delay::new(timeout), timeout)) }
+}
+
+/// Runs a future until it finishes or the default timeout passes
+///
+/// The future is never given up on if there is no
+/// [default timeout](crate::set_default_timeout) when this is called. This is
+/// how blocking functions with an executor of their own are timed out.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// ut::set_default_timeout(Some(Duration::from_millis(10)));
+/// let future = ut_runtime::timeout_default(std::future::pending::<()>());
+/// let err = tokio::runtime::Runtime::new().unwrap().block_on(future).unwrap_err();
+/// assert!(matches!(err, ut::BlockingError::Timeout(_)));
+/// ```
+pub fn timeout_default<F: Future>(future: F) -> Timeout<F> {
+  let limit = default_timeout().map(|timeout| (Delay::new(timeout), timeout));
+  Timeout { future: Box::pin(future), limit }
+}